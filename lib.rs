@@ -2,8 +2,49 @@
 
 #[ink::contract]
 mod red {
+    use ink::codegen::{EmitEvent, Env, TraitCallBuilder};
+    use ink::env::CallFlags;
     use ink::storage::Mapping;
-    use ink_prelude::string::String;
+    use ink_prelude::{
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    /// Maximum number of entries accepted by a single batch message.
+    const MAX_BATCH_SIZE: usize = 50;
+
+    /// Maximum length of the free-form `data` attached to transfers and approval callbacks.
+    const MAX_DATA_BYTES: usize = 256;
+
+    /// Denominator for fees expressed in basis points.
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// Role allowed to freeze and unfreeze accounts through `freeze_batch` and
+    /// `unfreeze_batch`.
+    const FREEZER_ROLE: RoleId = 1;
+
+    /// Role allowed to mint and burn through `bridge_mint` and `bridge_burn`.
+    const BRIDGE_ROLE: RoleId = 2;
+
+    /// Identifier of an access role granted with `grant_role`.
+    pub type RoleId = u32;
+
+    /// Semantic version of this contract code as `(major, minor, patch)`.
+    const VERSION: (u16, u16, u16) = (0, 1, 0);
+
+    /// Identifier of the storage layout, bumped whenever `RedToken` fields change.
+    const STORAGE_SCHEMA_ID: u32 = 46;
+
+    /// Milliseconds in a UTC day, for block timestamps.
+    const MS_PER_DAY: u64 = 24 * 60 * 60 * 1_000;
+
+    /// Milliseconds a scheduled account recovery waits before it can be executed.
+    const RECOVERY_DELAY_MS: u64 = 7 * MS_PER_DAY;
+
+    /// `OperationFailed` reason code: the caller is not the admin.
+    const REASON_NOT_ADMIN: u8 = 1;
 
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
@@ -22,17 +63,171 @@ mod red {
         SafeTransferCheckFailed(String),
     }
 
+    /// Result type of fallible RED messages, for contracts composing with them.
+    pub type PSP22Result<T> = Result<T, PSP22Error>;
+
+    /// An allowance value and the block it was granted in.
+    pub type ApprovalRecord = (Balance, BlockNumber);
+
+    /// A source chain id and the transaction on it that a bridge mint settles.
+    pub type BridgeTransferId = (u32, [u8; 32]);
+
+    /// Lets `?` turn a failed integer narrowing into the `Custom("overflow")` error
+    /// RED's own arithmetic uses.
+    impl From<core::num::TryFromIntError> for PSP22Error {
+        fn from(_: core::num::TryFromIntError) -> Self {
+            PSP22Error::Custom(String::from("overflow"))
+        }
+    }
+
+    /// A payment stream escrowing `deposit` tokens that accrue to `recipient` at
+    /// `rate_per_sec` from `start`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Stream {
+        pub sender: AccountId,
+        pub recipient: AccountId,
+        pub rate_per_sec: Balance,
+        pub deposit: Balance,
+        pub withdrawn: Balance,
+        pub start: Timestamp,
+    }
+
+    /// Tokens minted into escrow by `mint_vesting` that vest linearly over
+    /// `duration` milliseconds from `start`, with nothing released before `cliff`.
+    ///
+    /// `total` and `released` are in shares, so a rebase scales what is owed
+    /// exactly as it scales the escrowed shares.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct VestingSchedule {
+        pub total: Balance,
+        pub released: Balance,
+        pub start: Timestamp,
+        pub cliff: Timestamp,
+        pub duration: Timestamp,
+    }
+
+    /// Outcome of `transfer_with_receipt`, with the post-transfer balances of both sides.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TransferReceipt {
+        pub from: AccountId,
+        pub to: AccountId,
+        pub value: Balance,
+        pub block: BlockNumber,
+        pub new_sender_balance: Balance,
+        pub new_recipient_balance: Balance,
+    }
+
+    /// What a transfer would cost, as returned by `quote_transfer`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TransferQuote {
+        /// Amount `to` receives
+        pub net_to_recipient: Balance,
+        /// RED fee sent to `fee_recipient`
+        pub red_fee: Balance,
+        /// Total debited from the sender, fee included
+        pub total_debit: Balance,
+        /// Native fee `transfer_with_native_fee` requires on top
+        pub native_fee: Balance,
+        /// Whether the sender is exempt from the RED fee
+        pub exempt: bool,
+    }
+
+    /// An account's whole position, as returned by `account_info`.
+    ///
+    /// RED has no staking or income distribution, so `staked` and
+    /// `claimable_income` are always `0`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct AccountInfo {
+        pub balance: Balance,
+        pub staked: Balance,
+        pub claimable_income: Balance,
+        pub frozen: bool,
+        /// End of the lockup phase if it still blocks transfers from the account,
+        /// otherwise `0`.
+        pub locked_until: Timestamp,
+    }
+
+    /// Amounts a validated `transfer` or `transfer_from` settles with.
+    struct TransferPlan {
+        debit: Balance,
+        fee: Balance,
+        /// Shares `from` holds beforehand
+        from_shares: Balance,
+        /// Allowance left afterwards, or `None` for a plain `transfer` or an operator.
+        remaining_allowance: Option<Balance>,
+    }
+
+    /// How the transfer fee is applied to the transferred `value`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum FeeMode {
+        /// The fee is deducted from `value`; the recipient receives `value - fee`.
+        Inclusive,
+        /// The fee is charged on top; the sender pays `value + fee` and the
+        /// recipient receives `value`.
+        Exclusive,
+    }
+
+    /// Why tokens were burned, as carried by `Burned::reason_code`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(u8)]
+    pub enum BurnReason {
+        /// No specific reason; the code used by `burn`.
+        Generic = 0,
+        /// Tokens redeemed for the underlying asset.
+        Redemption = 1,
+        /// Tokens issued in error and corrected.
+        Correction = 2,
+        /// Tokens forfeited as a penalty.
+        Penalty = 3,
+    }
+
+    impl TryFrom<u8> for BurnReason {
+        type Error = PSP22Error;
+
+        fn try_from(code: u8) -> Result<Self, Self::Error> {
+            match code {
+                0 => Ok(Self::Generic),
+                1 => Ok(Self::Redemption),
+                2 => Ok(Self::Correction),
+                3 => Ok(Self::Penalty),
+                _ => Err(PSP22Error::Custom(String::from("unknown burn reason"))),
+            }
+        }
+    }
+
     /// Event emitted when a token transfer occurs.
+    ///
+    /// `seq` increases by one with every `Transfer`, including mints and burns, so
+    /// indexers can detect gaps.
     #[ink(event)]
     pub struct Transfer {
         from: Option<AccountId>,
         to: Option<AccountId>,
         value: Balance,
         data: Vec<u8>,
+        #[ink(topic)]
+        seq: u64,
     }
 
     /// Event emitted when an approval occurs that `spender` is allowed to withdraw",
     /// up to the amount of `value` tokens from `owner`.
+    ///
+    /// `value` is always the resulting allowance, never the change applied to it.
     #[ink(event)]
     pub struct Approval {
         owner: AccountId,
@@ -41,6 +236,275 @@ mod red {
         data: Vec<u8>,
     }
 
+    /// Event emitted before `Approval` whenever an allowance is set, spent or
+    /// migrated, with the allowance before and after for audit trails.
+    ///
+    /// `Approval` keeps the standard PSP22 fields.
+    #[ink(event)]
+    pub struct AllowanceChanged {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        old: Balance,
+        new: Balance,
+    }
+
+    /// Event emitted alongside `Approval { value: 0 }` when an approval to zero
+    /// revokes a nonzero allowance.
+    #[ink(event)]
+    pub struct Revocation {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+    }
+
+    /// Event emitted when the admin freezes an account.
+    #[ink(event)]
+    pub struct Frozen {
+        account: AccountId,
+    }
+
+    /// Event emitted when an account is added to or removed from the lockup allowlist.
+    #[ink(event)]
+    pub struct TransferAllowlistUpdated {
+        #[ink(topic)]
+        account: AccountId,
+        allowed: bool,
+    }
+
+    /// Event emitted when the admin unfreezes an account.
+    #[ink(event)]
+    pub struct Unfrozen {
+        account: AccountId,
+    }
+
+    /// Event emitted when the admin grants or revokes a role.
+    #[ink(event)]
+    pub struct RoleSet {
+        #[ink(topic)]
+        role: RoleId,
+        #[ink(topic)]
+        account: AccountId,
+        granted: bool,
+    }
+
+    /// Event emitted when the admin publishes a new reference price.
+    #[ink(event)]
+    pub struct PriceUpdated {
+        price: Balance,
+        timestamp: Timestamp,
+    }
+
+    /// Event emitted when the admin pauses the contract.
+    #[ink(event)]
+    pub struct Paused {}
+
+    /// Event emitted when the admin unpauses the contract.
+    #[ink(event)]
+    pub struct Unpaused {}
+
+    /// Event emitted when the admin freezes all transfers, e.g. for a legal hold.
+    #[ink(event)]
+    pub struct FrozenAll {
+        reason: String,
+    }
+
+    /// Event emitted when the admin lifts a `freeze_all`.
+    #[ink(event)]
+    pub struct UnfrozenAll {}
+
+    /// Event emitted alongside `Transfer` when tokens are sent with a sub-account tag.
+    #[ink(event)]
+    pub struct TaggedTransfer {
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        #[ink(topic)]
+        tag: [u8; 4],
+    }
+
+    /// Event emitted when a payment stream is created.
+    #[ink(event)]
+    pub struct StreamCreated {
+        #[ink(topic)]
+        stream_id: u32,
+        sender: AccountId,
+        recipient: AccountId,
+        rate_per_sec: Balance,
+        deposit: Balance,
+    }
+
+    /// Event emitted when the recipient withdraws accrued tokens from a stream.
+    #[ink(event)]
+    pub struct StreamWithdrawn {
+        #[ink(topic)]
+        stream_id: u32,
+        value: Balance,
+    }
+
+    /// Event emitted when the admin mints tokens into a vesting schedule.
+    #[ink(event)]
+    pub struct VestingCreated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        total: Balance,
+        cliff: Timestamp,
+        duration: Timestamp,
+    }
+
+    /// Event emitted when a beneficiary releases vested tokens.
+    #[ink(event)]
+    pub struct VestingReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when the sender cancels a stream.
+    #[ink(event)]
+    pub struct StreamCancelled {
+        #[ink(topic)]
+        stream_id: u32,
+        recipient_payout: Balance,
+        sender_refund: Balance,
+    }
+
+    /// Event emitted when the admin proposes a new fee recipient.
+    #[ink(event)]
+    pub struct FeeRecipientChangeProposed {
+        recipient: AccountId,
+    }
+
+    /// Event emitted when a proposed fee recipient accepts the role.
+    #[ink(event)]
+    pub struct FeeRecipientChanged {
+        old_recipient: AccountId,
+        new_recipient: AccountId,
+    }
+
+    /// Event emitted when the admin proposes a successor.
+    #[ink(event)]
+    pub struct AdminTransferProposed {
+        new_admin: AccountId,
+        expires_at: Timestamp,
+    }
+
+    /// Event emitted when a proposed admin accepts the role.
+    #[ink(event)]
+    pub struct AdminChanged {
+        old_admin: AccountId,
+        new_admin: AccountId,
+    }
+
+    /// Event emitted when the admin schedules moving `old`'s holdings to `new`.
+    #[ink(event)]
+    pub struct RecoveryScheduled {
+        #[ink(topic)]
+        old: AccountId,
+        new: AccountId,
+        ready_at: Timestamp,
+    }
+
+    /// Event emitted when a scheduled recovery moves `old`'s holdings to `new`.
+    #[ink(event)]
+    pub struct Recovered {
+        #[ink(topic)]
+        old: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when the admin rebases all balances by `factor_num / factor_den`.
+    #[ink(event)]
+    pub struct Rebased {
+        factor_num: u128,
+        factor_den: u128,
+        total_supply: Balance,
+    }
+
+    /// Event emitted when balances change without a `Transfer`, so indexers that
+    /// materialize balances know to refresh them.
+    ///
+    /// `account` is `None` when every balance changed, as in `rebase`; `old` and
+    /// `new` are then the total supply before and after.
+    #[ink(event)]
+    pub struct BalanceAdjusted {
+        #[ink(topic)]
+        account: Option<AccountId>,
+        old: Balance,
+        new: Balance,
+    }
+
+    /// Event emitted when `verify_post_upgrade` finds the supply state consistent.
+    #[ink(event)]
+    pub struct UpgradeVerified {
+        total_supply: Balance,
+        holder_count: u32,
+    }
+
+    /// Event emitted by every admin-gated message; `action_code` is its selector.
+    #[ink(event)]
+    pub struct AdminAction {
+        #[ink(topic)]
+        action_code: [u8; 4],
+        caller: AccountId,
+        block: BlockNumber,
+    }
+
+    /// Event emitted by `log_admin_precheck` for an admin operation that would fail.
+    ///
+    /// `op` is copied from the caller's input and is not checked against the
+    /// contract's messages.
+    #[ink(event)]
+    pub struct OperationFailed {
+        #[ink(topic)]
+        op: [u8; 4],
+        caller: AccountId,
+        reason_code: u8,
+    }
+
+    /// Event emitted by `burn` and `burn_with_reason`, next to the `Transfer` to `None`.
+    #[ink(event)]
+    pub struct Burned {
+        #[ink(topic)]
+        from: AccountId,
+        value: Balance,
+        /// A `BurnReason` code
+        reason_code: u8,
+    }
+
+    /// Event emitted when the bridge mints tokens locked or burned on `src_chain_id`.
+    #[ink(event)]
+    pub struct BridgeMint {
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        src_chain_id: u32,
+        src_tx: [u8; 32],
+    }
+
+    /// Event emitted when the bridge burns tokens to release them on `dst_chain_id`.
+    #[ink(event)]
+    pub struct BridgeBurn {
+        #[ink(topic)]
+        from: AccountId,
+        value: Balance,
+        dst_chain_id: u32,
+    }
+
+    /// Event emitted when an owner grants or revokes operator rights.
+    #[ink(event)]
+    pub struct OperatorSet {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        approved: bool,
+    }
+
     #[ink::trait_definition]
     pub trait PSP22 {
         #[ink(message)]
@@ -88,36 +552,207 @@ mod red {
         ) -> Result<(), PSP22Error>;
     }
 
+    /// Selectors are pinned to the PSP22 standard values so registries and other
+    /// contracts can call these messages without depending on RED's metadata.
     #[ink::trait_definition]
     pub trait PSP22Metadata {
-        #[ink(message)]
+        #[ink(message, selector = 0x3d261bd4)]
         fn token_name(&self) -> Option<String>;
 
-        #[ink(message)]
+        #[ink(message, selector = 0x34205be5)]
         fn token_symbol(&self) -> Option<String>;
 
-        #[ink(message)]
+        #[ink(message, selector = 0x7271b782)]
         fn token_decimals(&self) -> u8;
     }
 
+    /// Implemented by contracts that want to act on an allowance in the same
+    /// transaction it is granted, e.g. vaults pulling a deposit via `transfer_from`.
+    #[ink::trait_definition]
+    pub trait Spender {
+        #[ink(message)]
+        fn on_approval_received(
+            &mut self,
+            owner: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    /// Implemented by contracts that accept tokens sent to them with `transfer` or
+    /// `transfer_from`. `operator` is the account that made the call.
+    #[ink::trait_definition]
+    pub trait PSP22Receiver {
+        #[ink(message)]
+        fn before_received(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    /// Implemented by contracts borrowing through `flash_loan`. The receiver must
+    /// hold `amount + fee` when the callback returns.
+    #[ink::trait_definition]
+    pub trait FlashBorrower {
+        #[ink(message)]
+        fn on_flash_loan(
+            &mut self,
+            initiator: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
     #[ink(storage)]
     pub struct RedToken {
         /// The super user is the holder of all the tokens
         pub admin: AccountId,
-        /// Total token supply
+        /// Total token supply, in shares
         pub total_supply: Balance,
+        /// Balances in shares; `balance_of` scales them by the rebase factor
         pub balances: Mapping<AccountId, Balance>,
         pub allowances: Mapping<(AccountId, AccountId), Balance>,
         pub token_name: String,
         pub token_symbol: String,
         pub token_decimals: u8,
+        /// Accounts that can neither send nor receive tokens
+        pub frozen: Mapping<AccountId, bool>,
+        /// Reference price per token published by the admin, for display only
+        pub last_price: Balance,
+        /// Number of decimals `last_price` is expressed in
+        pub price_decimals: u8,
+        /// Upper bound on any single allowance
+        pub max_allowance: Balance,
+        /// Whether allowances above `max_allowance` are clamped instead of rejected
+        pub clamp_allowance: bool,
+        /// Whether transfers and approvals are currently halted
+        pub paused: bool,
+        /// Number of accounts holding a nonzero balance
+        pub holder_count: u32,
+        /// Fee in basis points applied by `get_amount_out`
+        pub swap_fee_bps: u16,
+        /// Lifetime total of tokens moved from one account to another
+        pub transferred_between: Mapping<(AccountId, AccountId), Balance>,
+        /// Accounts that have held a nonzero balance at some point; never cleared
+        pub ever_held: Mapping<AccountId, bool>,
+        /// Whether an owner may grant an allowance to itself
+        pub allow_self_approval: bool,
+        /// Active payment streams by id
+        pub streams: Mapping<u32, Stream>,
+        /// Id assigned to the next created stream
+        pub next_stream_id: u32,
+        /// Account fees are paid to
+        pub fee_recipient: AccountId,
+        /// Fee recipient proposed by the admin, awaiting acceptance
+        pub pending_fee_recipient: Option<AccountId>,
+        /// Accounts allowed to move an owner's tokens without a numeric allowance
+        pub operators: Mapping<(AccountId, AccountId), bool>,
+        /// Native currency backing the supply, paid out by `burn_and_redeem`
+        pub native_reserve: Balance,
+        /// Admin proposed by the current admin, awaiting acceptance
+        pub pending_admin: Option<AccountId>,
+        /// Block timestamp after which `pending_admin` can no longer accept
+        pub pending_admin_expires_at: Timestamp,
+        /// Seconds a proposed admin has to call `accept_admin`
+        pub admin_transfer_grace_secs: u64,
+        /// Whether `log_admin_precheck` records failed validations on-chain
+        pub audit_failed_ops: bool,
+        /// Running sum of all balances, kept in step by every balance write
+        pub balance_sum: Balance,
+        /// Native currency `transfer_with_native_fee` requires attached
+        pub native_fee_amount: Balance,
+        /// Fee in basis points taken from `transfer` and `transfer_from` amounts
+        pub transfer_fee_bps: u16,
+        /// Accounts whose transfers, sent or received, are not charged a fee
+        pub fee_exempt: Mapping<AccountId, bool>,
+        /// Block timestamp at which a pause set by `pause_until` lapses
+        pub pause_deadline: Option<Timestamp>,
+        /// Root of the airdrop merkle tree claimable via `claim_merkle`
+        pub merkle_root: Option<[u8; 32]>,
+        /// Airdrop leaves that have already been claimed
+        pub merkle_claimed: Mapping<[u8; 32], bool>,
+        /// Whether the transfer fee is deducted from or added to `value`
+        pub fee_mode: FeeMode,
+        /// Block timestamp until which only allowlisted accounts can send tokens
+        pub lockup_end: Timestamp,
+        /// Accounts that may send tokens during the lockup phase
+        pub transfer_allowlist: Mapping<AccountId, bool>,
+        /// Smallest nonzero allowance that can be granted
+        pub min_approval: Balance,
+        /// Most any account can send per UTC day, if limited
+        pub daily_limit: Option<Balance>,
+        /// Per-account `(day, volume)` sent on the most recent day with activity
+        pub daily_volume: Mapping<AccountId, (u64, Balance)>,
+        /// Number of admin-gated messages executed
+        pub admin_action_count: u64,
+        /// Latest allowance each owner granted each spender, and the block it was set in
+        pub approval_log: Mapping<(AccountId, AccountId), ApprovalRecord>,
+        /// Whether name and symbol are permanently locked
+        pub metadata_frozen: bool,
+        /// Whether transfers to the zero address burn instead of reverting
+        pub zero_address_burns: bool,
+        /// Lifetime total of tokens created, including the initial supply
+        pub total_minted: Balance,
+        /// Lifetime total of tokens destroyed
+        pub total_burned: Balance,
+        /// Contracts that may receive tokens through `transfer` and `transfer_from`
+        pub recipient_contract_allowlist: Mapping<AccountId, bool>,
+        /// Whether granted allowances are clamped to the owner's balance
+        pub cap_allowance_to_balance: bool,
+        /// Whether `token_name` and `token_symbol` return `None`
+        pub hide_metadata: bool,
+        /// Fee in basis points charged on flash loans, paid to `fee_recipient`
+        pub flash_fee_bps: u16,
+        /// Set while a flash loan callback runs
+        pub flash_loan_active: bool,
+        /// Scheduled recoveries: lost account to `(replacement, ready_at)`
+        pub pending_recoveries: Mapping<AccountId, (AccountId, Timestamp)>,
+        /// Whether transfers and mints require KYC-verified accounts
+        pub kyc_required: bool,
+        /// The account managing `kyc_verified`
+        pub kyc_admin: AccountId,
+        /// Accounts that passed KYC; absent means unverified
+        pub kyc_verified: Mapping<AccountId, bool>,
+        /// Tokens per share as `rebase_num / rebase_den`, in lowest terms
+        pub rebase_num: u128,
+        pub rebase_den: u128,
+        /// Holders of each role, kept as a list so they can be enumerated
+        pub role_holders: Mapping<RoleId, Vec<AccountId>>,
+        /// Whether transfers must move whole display units
+        pub require_whole_units: bool,
+        /// `seq` of the most recent `Transfer` event
+        pub transfer_seq: u64,
+        /// Whether a nonzero allowance must be reset to zero before `approve` changes it
+        pub require_zero_before_reapprove: bool,
+        /// Source-chain transactions already minted by `bridge_mint`
+        pub bridge_processed: Mapping<BridgeTransferId, bool>,
+        /// Whether `decrease_allowance` floors at zero instead of reverting
+        pub saturating_decrease: bool,
+        /// Sub-tokens deployed by `instantiate_child`, by deployment index
+        pub children: Mapping<u32, AccountId>,
+        /// Number of sub-tokens deployed, the next index in `children`
+        pub child_count: u32,
+        /// Why all transfers are frozen, if they are
+        pub freeze_reason: Option<String>,
+        /// Vesting schedules by beneficiary, escrowed by the contract account
+        pub vesting: Mapping<AccountId, VestingSchedule>,
+        /// Number of spenders each owner has a nonzero allowance for
+        pub approval_count: Mapping<AccountId, u32>,
+        /// Most spenders an owner may have a nonzero allowance for at once
+        pub max_approvals_per_owner: u32,
+        /// The account allowed to `pause` besides the admin
+        pub guardian: AccountId,
     }
 
     impl RedToken {
-        /// Initializes the token supply
+        /// Initializes the token supply, credited in full to `admin`
         #[ink(constructor)]
         pub fn new(init_supply: Balance, admin: AccountId, token_decimals: u8) -> Self {
-            Self {
+            let mut token = Self {
                 total_supply: init_supply,
                 admin,
                 balances: Default::default(),
@@ -125,315 +760,7698 @@ mod red {
                 token_name: "Real Estate DAO".to_string(),
                 token_symbol: "RED".to_string(),
                 token_decimals,
-            }
+                frozen: Default::default(),
+                last_price: 0,
+                price_decimals: 0,
+                max_allowance: Balance::MAX,
+                clamp_allowance: false,
+                paused: false,
+                holder_count: 0,
+                swap_fee_bps: 30,
+                transferred_between: Default::default(),
+                ever_held: Default::default(),
+                allow_self_approval: false,
+                streams: Default::default(),
+                next_stream_id: 0,
+                fee_recipient: admin,
+                pending_fee_recipient: None,
+                operators: Mapping::default(),
+                native_reserve: 0,
+                pending_admin: None,
+                pending_admin_expires_at: 0,
+                admin_transfer_grace_secs: 7 * 24 * 60 * 60,
+                audit_failed_ops: false,
+                balance_sum: 0,
+                native_fee_amount: 0,
+                transfer_fee_bps: 0,
+                fee_exempt: Default::default(),
+                pause_deadline: None,
+                merkle_root: None,
+                merkle_claimed: Default::default(),
+                fee_mode: FeeMode::Inclusive,
+                lockup_end: 0,
+                transfer_allowlist: Default::default(),
+                min_approval: 0,
+                daily_limit: None,
+                daily_volume: Default::default(),
+                admin_action_count: 0,
+                approval_log: Default::default(),
+                metadata_frozen: false,
+                zero_address_burns: false,
+                total_minted: init_supply,
+                total_burned: 0,
+                recipient_contract_allowlist: Default::default(),
+                cap_allowance_to_balance: false,
+                hide_metadata: false,
+                flash_fee_bps: 0,
+                flash_loan_active: false,
+                pending_recoveries: Default::default(),
+                kyc_required: false,
+                kyc_admin: admin,
+                kyc_verified: Default::default(),
+                rebase_num: 1,
+                rebase_den: 1,
+                role_holders: Default::default(),
+                require_whole_units: false,
+                transfer_seq: 0,
+                require_zero_before_reapprove: false,
+                bridge_processed: Default::default(),
+                saturating_decrease: false,
+                children: Default::default(),
+                child_count: 0,
+                freeze_reason: None,
+                vesting: Default::default(),
+                approval_count: Default::default(),
+                max_approvals_per_owner: u32::MAX,
+                guardian: admin,
+            };
+            token.write_shares(admin, 0, init_supply);
+            token
         }
-    }
 
-    impl PSP22 for RedToken {
-        /// Returns the total token supply
+        /// Returns the `seq` of the most recent `Transfer` event, `0` before the first.
         #[ink(message)]
-        fn total_supply(&self) -> Balance {
-            self.total_supply
+        pub fn transfer_seq(&self) -> u64 {
+            self.transfer_seq
         }
 
-        /// Returns the account balance for the specified `owner`
-        /// Returns `0` if the account is non-existent
+        /// Returns `true` if `account` holds `role`.
         #[ink(message)]
-        fn balance_of(&self, owner: AccountId) -> Balance {
-            self.balances.get(owner).unwrap_or(0)
+        pub fn has_role(&self, role: RoleId, account: AccountId) -> bool {
+            self.role_members(role).contains(&account)
         }
 
-        //  "Returns the amount which `spender` is still allowed to withdraw from `owner`.",
-        //  "Returns `0` if no allowance has been set."
+        /// Returns every account holding `role`, in the order they were granted it.
         #[ink(message)]
-        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
-            self.allowances.get((owner, spender)).unwrap_or(0)
+        pub fn role_members(&self, role: RoleId) -> Vec<AccountId> {
+            self.role_holders.get(role).unwrap_or_default()
         }
 
-        ///  "Transfers `value` amount of tokens from the caller's account to account `to`",
-        ///  "with additional `data` in unspecified format.",
-        ///  "",
-        ///  "On success a `Transfer` event is emitted.",
-        ///  "",
-        ///  "# Errors",
-        ///  "",
-        ///  "Reverts with error `InsufficientBalance` if there are not enough tokens on",
-        ///  "the caller's account Balance.",
-        ///  "",
-        ///  "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
-        ///  "",
-        ///  "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
-        ///  "Reverts with error `SafeTransferCheckFailed` if the recipient is a contract and
-        ///  rejected the transfer."
+        /// Grants `role` to `account`; granting a held role is a no-op.
+        ///
+        /// A role holds at most `MAX_BATCH_SIZE` members so `role_members` stays
+        /// cheap to read. Only callable by the admin. A `RoleSet` event is emitted.
         #[ink(message)]
-        fn transfer(
-            &mut self,
-            to: AccountId,
-            value: Balance,
-            data: Vec<u8>,
-        ) -> Result<(), PSP22Error> {
-            let sender = self.env().caller();
-            let sender_balance = self.balance_of(sender);
-
-            if sender_balance <= value {
-                return Err(PSP22Error::InsufficientBalance);
-            }
+        pub fn grant_role(&mut self, role: RoleId, account: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("grant_role"));
 
-            if sender == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroSenderAddress);
-            }
+            let mut members = self.role_members(role);
 
-            if to == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroRecipientAddress);
+            if members.contains(&account) {
+                return Ok(());
             }
 
-            if self.env().is_contract(&to) {
-                return Err(PSP22Error::SafeTransferCheckFailed(format!(
-                    "AccountId {:?} is contract",
-                    &to
-                )));
+            if members.len() >= MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("role full")));
             }
 
-            let recipient_balance = self.balance_of(to);
-
-            self.balances.insert(sender, &(sender_balance - value));
-            self.balances.insert(to, &(recipient_balance + value));
+            members.push(account);
+            self.role_holders.insert(role, &members);
 
-            self.env().emit_event(Transfer {
-                from: None,
-                to: Some(to),
-                value,
-                data,
+            self.env().emit_event(RoleSet {
+                role,
+                account,
+                granted: true,
             });
 
             Ok(())
         }
 
-        /// "Transfers `value` tokens on the behalf of `from` to the account `to`",
-        /// "with additional `data` in unspecified format.",
-        /// "",
-        /// "This can be used to allow a contract to transfer tokens on ones behalf and/or",
-        /// "to charge fees in sub-currencies, for example.",
-        /// "",
-        /// "On success a `Transfer` and `Approval` events are emitted.",
-        /// "",
-        /// "# Errors",
-        /// "",
-        /// "Reverts with error `InsufficientAllowance` if there are not enough tokens allowed",
-        /// "for the caller to withdraw from `from`.",
-        /// "",
-        /// "Reverts with error `InsufficientBalance` if there are not enough tokens on",
-        /// "the the account Balance of `from`.",
-        /// "",
-        /// "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
-        /// "",
-        /// "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        /// Revokes `role` from `account`; revoking a role not held is a no-op.
+        ///
+        /// Only callable by the admin. A `RoleSet` event is emitted.
         #[ink(message)]
-        fn transfer_from(
-            &mut self,
-            from: AccountId,
-            to: AccountId,
-            value: Balance,
-            data: Vec<u8>,
-        ) -> Result<(), PSP22Error> {
-            let caller = self.env().caller();
-            let allowance = self.allowance(from, caller);
+        pub fn revoke_role(&mut self, role: RoleId, account: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("revoke_role"));
 
-            if allowance < value {
-                return Err(PSP22Error::InsufficientBalance);
-            }
+            let mut members = self.role_members(role);
+            let Some(index) = members.iter().position(|member| *member == account) else {
+                return Ok(());
+            };
 
-            let from_balance = self.balance_of(from);
+            members.remove(index);
 
-            if from_balance < value {
-                return Err(PSP22Error::InsufficientBalance);
+            if members.is_empty() {
+                self.role_holders.remove(role);
+            } else {
+                self.role_holders.insert(role, &members);
             }
 
-            if from == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroSenderAddress);
-            }
+            self.env().emit_event(RoleSet {
+                role,
+                account,
+                granted: false,
+            });
 
-            if to == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroRecipientAddress);
-            }
+            Ok(())
+        }
 
-            let to_balance = self.balance_of(to);
-            self.balances.insert(from, &(from_balance - value));
-            self.balances.insert(to, &(to_balance + value));
+        /// Returns the semantic version of the deployed code as `(major, minor, patch)`.
+        #[ink(message)]
+        pub fn version(&self) -> (u16, u16, u16) {
+            VERSION
+        }
 
-            self.env().emit_event(Transfer {
-                from: Some(from),
-                to: None,
-                value,
-                data: data.clone(),
-            });
+        /// Returns the identifier of the storage layout the deployed code expects.
+        #[ink(message)]
+        pub fn storage_schema_id(&self) -> u32 {
+            STORAGE_SCHEMA_ID
+        }
 
-            self.env().emit_event(Approval {
-                owner: from,
-                spender: caller,
-                value,
-                data,
+        /// Returns the most entries a batch message accepts; longer batches revert
+        /// with `Custom("batch too large")` before doing any work.
+        #[ink(message)]
+        pub fn max_batch_size(&self) -> u32 {
+            MAX_BATCH_SIZE as u32
+        }
+
+        /// Returns the last published reference price per token.
+        #[ink(message)]
+        pub fn last_price(&self) -> Balance {
+            self.last_price
+        }
+
+        /// Returns the number of decimals the reference price is expressed in.
+        #[ink(message)]
+        pub fn price_decimals(&self) -> u8 {
+            self.price_decimals
+        }
+
+        /// Publishes a new reference price per token.
+        ///
+        /// The price is informational and never used in transfers. Only callable
+        /// by the admin. A `PriceUpdated` event carrying the block timestamp is emitted.
+        #[ink(message)]
+        pub fn set_price(&mut self, price: Balance) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_price"));
+
+            self.last_price = price;
+
+            self.env().emit_event(PriceUpdated {
+                price,
+                timestamp: self.env().block_timestamp(),
             });
 
             Ok(())
         }
 
-        ///    "Allows `spender` to withdraw from the caller's account multiple times, up to",
-        ///    "the `value` amount.",
-        ///    "",
-        ///    "If this function is called again it overwrites the current allowance with `value`.",
-        ///    "",
-        ///    "An `Approval` event is emitted.",
-        ///    "",
-        ///    "# Errors",
-        ///    "",
-        ///    "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
-        ///    "",
-        ///    "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        /// Sets the number of decimals the reference price is expressed in.
+        ///
+        /// Only callable by the admin.
         #[ink(message)]
-        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error> {
-            let caller = self.env().caller();
+        pub fn set_price_decimals(&mut self, price_decimals: u8) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_price_decimals"));
 
-            if caller == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroSenderAddress);
+            self.price_decimals = price_decimals;
+
+            Ok(())
+        }
+
+        /// Changes the number of token decimals.
+        ///
+        /// Only allowed before any tokens exist, since changing decimals afterwards would
+        /// silently rescale every displayed balance. Reverts with `Custom("decimals locked")`
+        /// once `total_supply` is nonzero. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_token_decimals(&mut self, token_decimals: u8) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_token_decimals"));
+
+            if self.total_supply > 0 {
+                return Err(PSP22Error::Custom(String::from("decimals locked")));
             }
 
-            if spender == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroRecipientAddress);
+            self.token_decimals = token_decimals;
+
+            Ok(())
+        }
+
+        /// Returns `true` once name and symbol can no longer change.
+        #[ink(message)]
+        pub fn metadata_frozen(&self) -> bool {
+            self.metadata_frozen
+        }
+
+        /// Changes the token name.
+        ///
+        /// Reverts with `Custom("metadata frozen")` after `freeze_metadata`. Only
+        /// callable by the admin.
+        #[ink(message)]
+        pub fn set_token_name(&mut self, token_name: String) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_token_name"));
+
+            if self.metadata_frozen {
+                return Err(PSP22Error::Custom(String::from("metadata frozen")));
             }
 
-            self.allowances.insert((caller, spender), &value);
+            self.token_name = token_name;
 
-            self.env().emit_event(Approval {
-                owner: caller,
-                spender,
-                value,
-                data: vec![],
-            });
+            Ok(())
+        }
+
+        /// Changes the token symbol.
+        ///
+        /// Reverts with `Custom("metadata frozen")` after `freeze_metadata`. Only
+        /// callable by the admin.
+        #[ink(message)]
+        pub fn set_token_symbol(&mut self, token_symbol: String) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_token_symbol"));
+
+            if self.metadata_frozen {
+                return Err(PSP22Error::Custom(String::from("metadata frozen")));
+            }
+
+            self.token_symbol = token_symbol;
 
             Ok(())
         }
 
-        ///    "Atomically increases the allowance granted to `spender` by the caller.",
-        //     "",
-        //     "An `Approval` event is emitted.",
-        //     "",
-        //     "# Errors",
-        //     "",
-        //     "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
-        //     "",
-        //     "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        /// Permanently locks name and symbol, e.g. once the token is listed.
+        ///
+        /// One-way; only callable by the admin.
         #[ink(message)]
-        fn increase_allowance(
+        pub fn freeze_metadata(&mut self) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("freeze_metadata"));
+
+            self.metadata_frozen = true;
+
+            Ok(())
+        }
+
+        /// Returns `true` if `token_name` and `token_symbol` return `None`.
+        #[ink(message)]
+        pub fn hide_metadata(&self) -> bool {
+            self.hide_metadata
+        }
+
+        /// Sets whether `token_name` and `token_symbol` advertise no metadata; the
+        /// stored values are kept and `token_decimals` is unaffected.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_hide_metadata(&mut self, hide: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_hide_metadata"));
+
+            self.hide_metadata = hide;
+
+            Ok(())
+        }
+
+        /// Splits `value` into its whole-token part and the fractional remainder in base units,
+        /// i.e. `(value / 10^decimals, value % 10^decimals)`.
+        ///
+        /// With zero decimals the fractional part is always `0`.
+        #[ink(message)]
+        pub fn format_amount(&self, value: Balance) -> (Balance, Balance) {
+            match 10u128.checked_pow(u32::from(self.token_decimals)) {
+                Some(scale) => (value / scale, value % scale),
+                // The scale exceeds any representable balance, so nothing is a whole token.
+                None => (0, value),
+            }
+        }
+
+        /// Converts `value` in RED base units to an amount with `to_decimals`
+        /// decimals, e.g. for a wrapped 18-decimal companion token.
+        ///
+        /// Scaling down truncates the part that can't be represented. Reverts with
+        /// `Custom("overflow")` if the result doesn't fit in a `Balance`.
+        #[ink(message)]
+        pub fn scale_to(&self, value: Balance, to_decimals: u8) -> Result<Balance, PSP22Error> {
+            Self::rescale(value, self.token_decimals, to_decimals)
+        }
+
+        /// Converts `value` with `from_decimals` decimals to RED base units, the
+        /// inverse of `scale_to`.
+        #[ink(message)]
+        pub fn scale_from(&self, value: Balance, from_decimals: u8) -> Result<Balance, PSP22Error> {
+            Self::rescale(value, from_decimals, self.token_decimals)
+        }
+
+        /// Returns `true` if transfers must move whole display units.
+        #[ink(message)]
+        pub fn require_whole_units(&self) -> bool {
+            self.require_whole_units
+        }
+
+        /// Sets whether `transfer` and `transfer_from` reject amounts not divisible by
+        /// `10^decimals` with `Custom("whole units only")`. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_require_whole_units(&mut self, required: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_require_whole_units"));
+
+            self.require_whole_units = required;
+
+            Ok(())
+        }
+
+        /// Returns the maximum allowance any spender can be granted.
+        #[ink(message)]
+        pub fn max_allowance(&self) -> Balance {
+            self.max_allowance
+        }
+
+        /// Sets the maximum allowance any spender can be granted.
+        ///
+        /// If `clamp` is `true`, approvals above the cap are reduced to it, otherwise
+        /// they revert with `Custom("allowance too high")`. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_max_allowance(
             &mut self,
-            spender: AccountId,
-            delta_value: Balance,
+            max_allowance: Balance,
+            clamp: bool,
         ) -> Result<(), PSP22Error> {
-            let caller = self.env().caller();
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_max_allowance"));
 
-            if caller == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroSenderAddress);
-            }
+            self.max_allowance = max_allowance;
+            self.clamp_allowance = clamp;
 
-            if spender == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroRecipientAddress);
-            }
+            Ok(())
+        }
 
-            let current_allowence = self.allowance(caller, spender);
-            self.allowances
-                .insert((caller, spender), &(current_allowence + delta_value));
+        /// Returns `true` if granted allowances are clamped to the owner's balance.
+        #[ink(message)]
+        pub fn cap_allowance_to_balance(&self) -> bool {
+            self.cap_allowance_to_balance
+        }
 
-            self.env().emit_event(Approval {
-                owner: caller,
-                spender,
-                value: delta_value,
-                data: vec![],
-            });
+        /// Sets whether `approve` and `increase_allowance` clamp the resulting
+        /// allowance to the owner's current balance.
+        ///
+        /// The clamp applies when the allowance is granted only: a later drop in the
+        /// owner's balance does not shrink existing allowances, and
+        /// `increase_allowance` never lowers one. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_cap_allowance_to_balance(&mut self, enabled: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_cap_allowance_to_balance"));
+
+            self.cap_allowance_to_balance = enabled;
 
             Ok(())
         }
 
-        ///    "Atomically decreases the allowance granted to `spender` by the caller.",
-        //     "",
-        //     "An `Approval` event is emitted.",
-        //     "",
-        //     "# Errors",
-        //     "",
-        //     "Reverts with error `InsufficientAllowance` if there are not enough tokens allowed",
-        //     "by owner for `spender`.",
-        //     "",
-        //     "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
-        //     "",
-        //     "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        /// Returns `true` if `approve` requires a zero allowance before setting a new
+        /// nonzero one.
         #[ink(message)]
-        fn decrease_allowance(
+        pub fn require_zero_before_reapprove(&self) -> bool {
+            self.require_zero_before_reapprove
+        }
+
+        /// Sets whether `approve` to a nonzero value reverts with
+        /// `Custom("allowance not zero")` while the current allowance is nonzero.
+        ///
+        /// This closes the approval race where a spender front-runs a change to
+        /// spend both the old and the new allowance. Off by default; only callable by
+        /// the admin.
+        #[ink(message)]
+        pub fn set_require_zero_before_reapprove(
             &mut self,
-            spender: AccountId,
-            delta_value: Balance,
+            required: bool,
         ) -> Result<(), PSP22Error> {
-            let caller = self.env().caller();
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_require_zero_before_reapprove"));
 
-            let current_allowence = self.allowance(caller, spender);
+            self.require_zero_before_reapprove = required;
 
-            if current_allowence < delta_value {
-                return Err(PSP22Error::InsufficientAllowance);
-            }
+            Ok(())
+        }
 
-            if caller == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroSenderAddress);
-            }
+        /// Returns `true` if `decrease_allowance` floors at zero.
+        #[ink(message)]
+        pub fn saturating_decrease(&self) -> bool {
+            self.saturating_decrease
+        }
 
-            if spender == AccountId::from([0u8; 32]) {
-                return Err(PSP22Error::ZeroRecipientAddress);
+        /// Sets whether `decrease_allowance` by more than the current allowance sets
+        /// it to zero instead of reverting with `InsufficientAllowance`. Only callable
+        /// by the admin.
+        #[ink(message)]
+        pub fn set_saturating_decrease(&mut self, enabled: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_saturating_decrease"));
+
+            self.saturating_decrease = enabled;
+
+            Ok(())
+        }
+
+        /// Returns how many spenders `owner` has a nonzero allowance for.
+        #[ink(message)]
+        pub fn approval_count(&self, owner: AccountId) -> u32 {
+            self.approval_count.get(owner).unwrap_or(0)
+        }
+
+        /// Returns the most spenders an owner may have a nonzero allowance for.
+        #[ink(message)]
+        pub fn max_approvals_per_owner(&self) -> u32 {
+            self.max_approvals_per_owner
+        }
+
+        /// Sets the most spenders an owner may have a nonzero allowance for. New
+        /// nonzero approvals past it revert with `Custom("too many approvals")`;
+        /// existing ones are kept. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_max_approvals_per_owner(&mut self, max: u32) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_max_approvals_per_owner"));
+
+            self.max_approvals_per_owner = max;
+
+            Ok(())
+        }
+
+        /// Returns the smallest nonzero allowance that can be granted.
+        #[ink(message)]
+        pub fn min_approval(&self) -> Balance {
+            self.min_approval
+        }
+
+        /// Sets the smallest nonzero allowance that can be granted.
+        ///
+        /// Approvals requesting a smaller nonzero allowance revert with
+        /// `Custom("approval too small")`; revoking to zero is always allowed. The
+        /// floor applies to the request, not to what `cap_allowance_to_balance`
+        /// clamps it to. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_min_approval(&mut self, min_approval: Balance) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_min_approval"));
+
+            self.min_approval = min_approval;
+
+            Ok(())
+        }
+
+        /// Returns the most any account can send per UTC day, if limited.
+        #[ink(message)]
+        pub fn daily_limit(&self) -> Option<Balance> {
+            self.daily_limit
+        }
+
+        /// Sets the most any account can send per UTC day; `None` removes the limit.
+        ///
+        /// Counted are `transfer`, `transfer_from` and deposits into `create_stream`.
+        /// Payouts the contract makes from escrow (`withdraw_stream`, `cancel_stream`,
+        /// `release`) and admin or bridge movements (`recover_account`,
+        /// `bridge_burn`, flash loan repayment) are not counted against anyone.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_daily_limit(&mut self, daily_limit: Option<Balance>) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_daily_limit"));
+
+            self.daily_limit = daily_limit;
+
+            Ok(())
+        }
+
+        /// Returns how much `account` has sent so far in the current UTC day.
+        #[ink(message)]
+        pub fn daily_volume(&self, account: AccountId) -> Balance {
+            let today = self.env().block_timestamp() / MS_PER_DAY;
+
+            match self.daily_volume.get(account) {
+                Some((day, volume)) if day == today => volume,
+                _ => 0,
             }
+        }
+
+        /// Returns `true` if transfers to the zero address burn the tokens.
+        #[ink(message)]
+        pub fn zero_address_burns(&self) -> bool {
+            self.zero_address_burns
+        }
 
-            self.allowances
-                .insert((caller, spender), &(current_allowence - delta_value));
+        /// Makes `transfer` and `transfer_from` to the zero address burn the tokens,
+        /// ERC-20 style, instead of reverting with `ZeroRecipientAddress`.
+        ///
+        /// Burns are not charged a fee and don't count towards the daily limit.
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_zero_address_burns(&mut self, enabled: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_zero_address_burns"));
 
-            self.env().emit_event(Approval {
-                owner: caller,
-                spender,
-                value: delta_value,
-                data: vec![],
-            });
+            self.zero_address_burns = enabled;
 
             Ok(())
         }
-    }
 
-    impl PSP22Metadata for RedToken {
-        /// Returns the token name.
+        /// Allows or forbids owners granting an allowance to themselves.
+        ///
+        /// Only callable by the admin.
         #[ink(message)]
-        fn token_name(&self) -> Option<String> {
-            Some(self.token_name.clone())
+        pub fn set_allow_self_approval(&mut self, allow: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_allow_self_approval"));
+
+            self.allow_self_approval = allow;
+
+            Ok(())
         }
 
-        /// Returns the token symbol.
+        /// Returns the number of admin-gated messages executed so far.
         #[ink(message)]
-        fn token_symbol(&self) -> Option<String> {
-            Some(self.token_symbol.clone())
+        pub fn admin_action_count(&self) -> u64 {
+            self.admin_action_count
         }
 
-        /// Returns the token decimals.
+        /// Enables or disables on-chain logging of failed admin operations.
+        ///
+        /// Only callable by the admin.
         #[ink(message)]
-        fn token_decimals(&self) -> u8 {
-            self.token_decimals
+        pub fn set_audit_failed_ops(&mut self, enabled: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_audit_failed_ops"));
+
+            self.audit_failed_ops = enabled;
+
+            Ok(())
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use ink::primitives::AccountId;
+        /// Validates an admin-gated operation `op` for the caller without performing it.
+        ///
+        /// Returns the reason code the operation would fail with, or `None` if it would
+        /// pass. When `audit_failed_ops` is set, a failure also emits `OperationFailed`.
+        ///
+        /// `op` is unvalidated caller input: only the caller's admin status is
+        /// checked, so any four bytes are accepted and logged as given, whether or not
+        /// they are the selector of an admin message. Consumers of the log must not
+        /// treat `op` as proof that such an operation exists or was attempted.
+        ///
+        /// A reverted call discards its events, so the admin messages themselves can't
+        /// log their own failures. Callers wanting an audit trail submit this message as
+        /// a separate transaction before the operation; it never reverts, so the event
+        /// is kept.
+        #[ink(message)]
+        pub fn log_admin_precheck(&mut self, op: [u8; 4]) -> Option<u8> {
+            let caller = self.env().caller();
 
-        use super::*;
+            if caller == self.admin {
+                return None;
+            }
 
-        #[test]
-        fn test_init() {
-            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 5u8);
-            assert_eq!(
-                contract.token_name().unwrap(),
-                "Real Estate DAO".to_string()
-            );
+            if self.audit_failed_ops {
+                self.env().emit_event(OperationFailed {
+                    op,
+                    caller,
+                    reason_code: REASON_NOT_ADMIN,
+                });
+            }
+
+            Some(REASON_NOT_ADMIN)
+        }
+
+        /// Returns the lifetime total of tokens minted, including the initial supply.
+        #[ink(message)]
+        pub fn total_minted(&self) -> Balance {
+            self.total_minted
+        }
+
+        /// Returns the lifetime total of tokens burned.
+        #[ink(message)]
+        pub fn total_burned(&self) -> Balance {
+            self.total_burned
+        }
+
+        /// Returns the running sum of all balances, in shares.
+        #[ink(message)]
+        pub fn balance_sum(&self) -> Balance {
+            self.balance_sum
+        }
+
+        /// Returns `true` if the sum of all balances equals `total_supply`.
+        ///
+        /// `Mapping` can't be enumerated, so this compares against the running
+        /// `balance_sum` rather than re-reading every balance.
+        #[ink(message)]
+        pub fn check_supply_invariant(&self) -> bool {
+            self.balance_sum == self.total_supply
+        }
+
+        /// Returns `true` if the contract is paused.
+        ///
+        /// A pause set by `pause_until` reads as `false` once its deadline has passed.
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+                && self
+                    .pause_deadline
+                    .is_none_or(|deadline| self.env().block_timestamp() < deadline)
+        }
+
+        /// Returns the timestamp at which the current pause lapses, if it has one.
+        #[ink(message)]
+        pub fn pause_deadline(&self) -> Option<Timestamp> {
+            self.pause_deadline
+        }
+
+        /// Returns the number of accounts holding a nonzero balance.
+        #[ink(message)]
+        pub fn holder_count(&self) -> u32 {
+            self.holder_count
+        }
+
+        /// Checks that storage decoded sensibly after a code upgrade.
+        ///
+        /// Reverts with `Custom("supply mismatch")` unless `balance_sum` equals
+        /// `total_supply`, and with `Custom("implausible holder count")` unless there
+        /// are holders exactly when there is supply, at most one per share. Only
+        /// callable by the admin. An `UpgradeVerified` event is emitted on success.
+        #[ink(message)]
+        pub fn verify_post_upgrade(&mut self) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("verify_post_upgrade"));
+
+            if !self.check_supply_invariant() {
+                return Err(PSP22Error::Custom(String::from("supply mismatch")));
+            }
+
+            let holders = Balance::from(self.holder_count);
+
+            if (holders == 0) != (self.balance_sum == 0) || holders > self.balance_sum {
+                return Err(PSP22Error::Custom(String::from("implausible holder count")));
+            }
+
+            self.env().emit_event(UpgradeVerified {
+                total_supply: self.total_supply(),
+                holder_count: self.holder_count,
+            });
+
+            Ok(())
+        }
+
+        /// Returns `(paused, total_supply, holder_count)` in a single read for monitoring.
+        #[ink(message)]
+        pub fn health(&self) -> (bool, Balance, u32) {
+            (self.paused(), self.total_supply(), self.holder_count)
+        }
+
+        /// Returns the rebase factor as `(num, den)`: each share is worth `num / den`
+        /// tokens.
+        #[ink(message)]
+        pub fn rebase_factor(&self) -> (u128, u128) {
+            (self.rebase_num, self.rebase_den)
+        }
+
+        /// Returns the shares held by `owner`.
+        #[ink(message)]
+        pub fn shares_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or(0)
+        }
+
+        /// Scales every balance and the total supply by `factor_num / factor_den`,
+        /// e.g. after a property revaluation, without touching any account.
+        ///
+        /// Balances are stored as shares and only the share price changes. Amounts
+        /// are converted to whole shares rounding down, so after a rebase transfers,
+        /// mints and burns move the value of whole shares: events report that value,
+        /// an amount worth less than one share reverts with
+        /// `Custom("amount below one share")`, and moving an account's whole
+        /// balance moves all of its shares. Allowances, limits and other recorded
+        /// amounts are not rescaled. Reverts with `Custom("invalid factor")` if either
+        /// part is zero. Only callable by the admin. `Rebased` and `BalanceAdjusted`
+        /// events are emitted.
+        #[ink(message)]
+        pub fn rebase(&mut self, factor_num: u128, factor_den: u128) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("rebase"));
+
+            if factor_num == 0 || factor_den == 0 {
+                return Err(PSP22Error::Custom(String::from("invalid factor")));
+            }
+
+            let overflow = || PSP22Error::Custom(String::from("overflow"));
+            let num = self
+                .rebase_num
+                .checked_mul(factor_num)
+                .ok_or_else(overflow)?;
+            let den = self
+                .rebase_den
+                .checked_mul(factor_den)
+                .ok_or_else(overflow)?;
+            let divisor = Self::gcd(num, den);
+            let old_supply = self.total_supply();
+            self.rebase_num = num / divisor;
+            self.rebase_den = den / divisor;
+
+            self.env().emit_event(Rebased {
+                factor_num,
+                factor_den,
+                total_supply: self.total_supply(),
+            });
+            self.env().emit_event(BalanceAdjusted {
+                account: None,
+                old: old_supply,
+                new: self.total_supply(),
+            });
+
+            Ok(())
+        }
+
+        /// Returns the guardian, who can `pause` but not `unpause`.
+        #[ink(message)]
+        pub fn guardian(&self) -> AccountId {
+            self.guardian
+        }
+
+        /// Makes `guardian` the account allowed to `pause` besides the admin. Only
+        /// callable by the admin.
+        ///
+        /// The guardian starts out as the admin and follows the admin role through
+        /// `accept_admin` until set to another account.
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_guardian"));
+
+            self.guardian = guardian;
+
+            Ok(())
+        }
+
+        /// Halts transfers and approvals until `unpause` is called.
+        ///
+        /// Callable by the admin or the guardian. A `Paused` event is emitted.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), PSP22Error> {
+            if self.env().caller() != self.guardian {
+                self.ensure_admin()?;
+            }
+            self.record_admin_action(ink::selector_bytes!("pause"));
+
+            self.paused = true;
+            self.pause_deadline = None;
+            self.env().emit_event(Paused {});
+
+            Ok(())
+        }
+
+        /// Halts transfers and approvals until `deadline`, or an earlier `unpause`.
+        ///
+        /// Guards against an emergency pause being forgotten. Only callable by the
+        /// admin. A `Paused` event is emitted.
+        #[ink(message)]
+        pub fn pause_until(&mut self, deadline: Timestamp) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("pause_until"));
+
+            if deadline <= self.env().block_timestamp() {
+                return Err(PSP22Error::Custom(String::from("deadline passed")));
+            }
+
+            self.paused = true;
+            self.pause_deadline = Some(deadline);
+            self.env().emit_event(Paused {});
+
+            Ok(())
+        }
+
+        /// Resumes transfers and approvals.
+        ///
+        /// Only callable by the admin. An `Unpaused` event is emitted.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("unpause"));
+
+            self.paused = false;
+            self.pause_deadline = None;
+            self.env().emit_event(Unpaused {});
+
+            Ok(())
+        }
+
+        /// Returns why all transfers are frozen, or `None` if they aren't.
+        #[ink(message)]
+        pub fn freeze_reason(&self) -> Option<String> {
+            self.freeze_reason.clone()
+        }
+
+        /// Blocks every transfer until `unfreeze_all`, recording `reason` for the
+        /// audit trail, e.g. a court order reference.
+        ///
+        /// Stream payouts, vesting releases, flash loans, burns and account recovery
+        /// are blocked as well. Unlike `pause`, approvals and mints stay possible and
+        /// the reason is kept on-chain.
+        /// Freezing again replaces the reason. Reverts with `Custom("data too large")`
+        /// if `reason` exceeds the data size limit. Only callable by the admin. A
+        /// `FrozenAll` event is emitted.
+        #[ink(message)]
+        pub fn freeze_all(&mut self, reason: String) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("freeze_all"));
+            Self::ensure_data_size(reason.as_bytes())?;
+
+            self.freeze_reason = Some(reason.clone());
+            self.env().emit_event(FrozenAll { reason });
+
+            Ok(())
+        }
+
+        /// Lifts a `freeze_all`. Only callable by the admin. An `UnfrozenAll` event is
+        /// emitted.
+        #[ink(message)]
+        pub fn unfreeze_all(&mut self) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("unfreeze_all"));
+
+            self.freeze_reason = None;
+            self.env().emit_event(UnfrozenAll {});
+
+            Ok(())
+        }
+
+        /// Sets the allowance of `spender` like `approve`, then calls
+        /// `Spender::on_approval_received(owner, value, data)` on `spender`.
+        ///
+        /// The callee may re-enter this contract, e.g. to pull the tokens with
+        /// `transfer_from`. If the callback fails or returns an error the whole call
+        /// reverts, including the allowance. An `Approval` event is emitted.
+        #[ink(message)]
+        pub fn approve_and_call(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            Self::ensure_data_size(&data)?;
+            PSP22::approve(self, spender, value)?;
+
+            let owner = self.env().caller();
+            let callback_failed = || PSP22Error::Custom(String::from("approval callback failed"));
+
+            let mut receiver: ink::contract_ref!(Spender) = spender.into();
+            receiver
+                .call_mut()
+                .on_approval_received(owner, value, data)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke()
+                .map_err(|_| callback_failed())?
+                .map_err(|_| callback_failed())?
+        }
+
+        /// Sets the allowance of `spender` like `approve`, but the emitted `Approval`
+        /// event carries `data`, mirroring the `data` argument of `transfer`.
+        #[ink(message)]
+        pub fn approve_with_data(
+            &mut self,
+            spender: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            Self::ensure_data_size(&data)?;
+
+            let caller = self.env().caller();
+
+            if caller == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroSenderAddress);
+            }
+
+            if spender == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            self.ensure_not_self_approval(caller, spender)?;
+
+            let previous = self.allowance(caller, spender);
+
+            if self.require_zero_before_reapprove && value != 0 && previous != 0 {
+                return Err(PSP22Error::Custom(String::from("allowance not zero")));
+            }
+
+            let revoked = value == 0 && previous != 0;
+            let value = self.capped_allowance(caller, value)?;
+            self.set_approval(caller, spender, previous, value)?;
+
+            self.env().emit_event(Approval {
+                owner: caller,
+                spender,
+                value,
+                data,
+            });
+
+            if revoked {
+                self.env().emit_event(Revocation {
+                    owner: caller,
+                    spender,
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Sets the caller's allowance for `spender` to zero, like `approve(spender, 0)`.
+        ///
+        /// `Approval` and, if the allowance was nonzero, `Revocation` events are
+        /// emitted.
+        #[ink(message)]
+        pub fn revoke_approval(&mut self, spender: AccountId) -> Result<(), PSP22Error> {
+            self.approve_with_data(spender, 0, vec![])
+        }
+
+        /// Transfers `value` tokens to `to` like `transfer` and returns the caller's
+        /// remaining balance, saving wallets a `balance_of` round-trip.
+        ///
+        /// `transfer` itself keeps the standard PSP22 signature.
+        #[ink(message)]
+        pub fn transfer_with_balance(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<Balance, PSP22Error> {
+            PSP22::transfer(self, to, value, data)?;
+
+            Ok(self.balance_of(self.env().caller()))
+        }
+
+        /// Transfers `value` tokens from `from` to `to` like `transfer_from` and
+        /// returns the caller's remaining allowance, or `Balance::MAX` for an
+        /// operator, whose allowance is unlimited.
+        ///
+        /// `transfer_from` itself keeps the standard PSP22 signature.
+        #[ink(message)]
+        pub fn transfer_from_with_allowance(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<Balance, PSP22Error> {
+            PSP22::transfer_from(self, from, to, value, data)?;
+
+            let caller = self.env().caller();
+
+            if self.is_operator(from, caller) {
+                return Ok(Balance::MAX);
+            }
+
+            Ok(self.allowance(from, caller))
+        }
+
+        /// Returns the native fee `transfer_with_native_fee` requires.
+        #[ink(message)]
+        pub fn native_fee_amount(&self) -> Balance {
+            self.native_fee_amount
+        }
+
+        /// Sets the native fee `transfer_with_native_fee` requires.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_native_fee_amount(&mut self, amount: Balance) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_native_fee_amount"));
+
+            self.native_fee_amount = amount;
+
+            Ok(())
+        }
+
+        /// Returns the error `transfer_from(from, to, value, _)` by the caller would
+        /// fail with, or `Ok(())` if it would succeed, without changing any state.
+        ///
+        /// The checks are the ones `transfer_from` itself runs: pause, allowance,
+        /// balance, addresses, freezes, lockup, daily limit and the recipient contract
+        /// allowlist. Whether an allowed contract accepts through `PSP22Receiver` is
+        /// not asked.
+        #[ink(message)]
+        pub fn can_transfer_from(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            self.validate_transfer_from(self.env().caller(), from, to, value)
+                .map(|_| ())
+        }
+
+        /// Returns what a `transfer(to, value, _)` by the caller would cost, computed
+        /// like the transfer itself under the current fee mode and exemptions.
+        ///
+        /// Only the amounts are quoted; use `can_transfer_from` to check whether the
+        /// transfer would succeed.
+        #[ink(message)]
+        pub fn quote_transfer(
+            &self,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<TransferQuote, PSP22Error> {
+            let caller = self.env().caller();
+            let (debit, fee) = self.transfer_debit(caller, to, value)?;
+
+            Ok(TransferQuote {
+                net_to_recipient: debit - fee,
+                red_fee: fee,
+                total_debit: debit,
+                native_fee: self.native_fee_amount,
+                exempt: self.is_fee_exempt(caller),
+            })
+        }
+
+        /// Transfers `value` tokens to `to` like `transfer` and returns a receipt with
+        /// the block number and the resulting balances of both accounts.
+        #[ink(message)]
+        pub fn transfer_with_receipt(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<TransferReceipt, PSP22Error> {
+            PSP22::transfer(self, to, value, data)?;
+            let from = self.env().caller();
+
+            Ok(TransferReceipt {
+                from,
+                to,
+                value,
+                block: self.env().block_number(),
+                new_sender_balance: self.balance_of(from),
+                new_recipient_balance: self.balance_of(to),
+            })
+        }
+
+        /// Transfers `value` tokens to `to` like `transfer`, paying the fee in native
+        /// currency so the RED amount arrives in full.
+        ///
+        /// At least `native_fee_amount` must be attached; the whole attached value is
+        /// forwarded to `fee_recipient`. Reverts with `Custom("insufficient native fee")`
+        /// otherwise.
+        #[ink(message, payable)]
+        pub fn transfer_with_native_fee(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let fee = self.env().transferred_value();
+
+            if fee < self.native_fee_amount {
+                return Err(PSP22Error::Custom(String::from("insufficient native fee")));
+            }
+
+            PSP22::transfer(self, to, value, data)?;
+
+            if fee > 0 {
+                self.env()
+                    .transfer(self.fee_recipient, fee)
+                    .map_err(|_| PSP22Error::Custom(String::from("native transfer failed")))?;
+            }
+
+            Ok(())
+        }
+
+        /// Transfers `value` tokens to `to` like `transfer`, tagging it with a 4-byte
+        /// sub-account identifier the recipient can use for internal routing.
+        ///
+        /// The balance is credited to `to`; the tag is informational only.
+        /// On success a `Transfer` and a `TaggedTransfer` event are emitted.
+        #[ink(message)]
+        pub fn transfer_tagged(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            tag: [u8; 4],
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            PSP22::transfer(self, to, value, data)?;
+
+            self.env().emit_event(TaggedTransfer {
+                from: self.env().caller(),
+                to,
+                value,
+                tag,
+            });
+
+            Ok(())
+        }
+
+        /// Transfers `whole` tokens plus `fraction` base units to `to` like `transfer`,
+        /// i.e. `whole * 10^decimals + fraction`, the inverse of `format_amount`.
+        ///
+        /// Reverts with `Custom("fraction too large")` unless `fraction < 10^decimals`.
+        #[ink(message)]
+        pub fn transfer_display(
+            &mut self,
+            to: AccountId,
+            whole: Balance,
+            fraction: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let overflow = || PSP22Error::Custom(String::from("overflow"));
+
+            let value = match 10u128.checked_pow(u32::from(self.token_decimals)) {
+                Some(scale) => {
+                    if fraction >= scale {
+                        return Err(PSP22Error::Custom(String::from("fraction too large")));
+                    }
+
+                    whole
+                        .checked_mul(scale)
+                        .and_then(|base| base.checked_add(fraction))
+                        .ok_or_else(overflow)?
+                }
+                // No whole token is representable, as in `format_amount`.
+                None if whole == 0 => fraction,
+                None => return Err(overflow()),
+            };
+
+            PSP22::transfer(self, to, value, data)
+        }
+
+        /// Returns the fee in basis points applied by `get_amount_out`.
+        #[ink(message)]
+        pub fn swap_fee_bps(&self) -> u16 {
+            self.swap_fee_bps
+        }
+
+        /// Sets the fee in basis points applied by `get_amount_out`.
+        ///
+        /// Only callable by the admin. Fees above 100% are rejected.
+        #[ink(message)]
+        pub fn set_swap_fee_bps(&mut self, swap_fee_bps: u16) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_swap_fee_bps"));
+
+            if u128::from(swap_fee_bps) > BPS_DENOMINATOR {
+                return Err(PSP22Error::Custom(String::from("fee too high")));
+            }
+
+            self.swap_fee_bps = swap_fee_bps;
+
+            Ok(())
+        }
+
+        /// Returns the fee in basis points taken from transfers.
+        #[ink(message)]
+        pub fn transfer_fee_bps(&self) -> u16 {
+            self.transfer_fee_bps
+        }
+
+        /// Sets the fee in basis points taken from `transfer` and `transfer_from`.
+        ///
+        /// Only callable by the admin. Fees above 100% are rejected.
+        #[ink(message)]
+        pub fn set_transfer_fee_bps(&mut self, transfer_fee_bps: u16) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_transfer_fee_bps"));
+
+            if u128::from(transfer_fee_bps) > BPS_DENOMINATOR {
+                return Err(PSP22Error::Custom(String::from("fee too high")));
+            }
+
+            self.transfer_fee_bps = transfer_fee_bps;
+
+            Ok(())
+        }
+
+        /// Returns the fee in basis points charged on flash loans.
+        #[ink(message)]
+        pub fn flash_fee_bps(&self) -> u16 {
+            self.flash_fee_bps
+        }
+
+        /// Sets the fee in basis points charged on `flash_loan`.
+        ///
+        /// Only callable by the admin. Fees above 100% are rejected.
+        #[ink(message)]
+        pub fn set_flash_fee_bps(&mut self, flash_fee_bps: u16) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_flash_fee_bps"));
+
+            if u128::from(flash_fee_bps) > BPS_DENOMINATOR {
+                return Err(PSP22Error::Custom(String::from("fee too high")));
+            }
+
+            self.flash_fee_bps = flash_fee_bps;
+
+            Ok(())
+        }
+
+        /// Returns the fee `flash_loan` charges for borrowing `amount`.
+        #[ink(message)]
+        pub fn flash_fee(&self, amount: Balance) -> Result<Balance, PSP22Error> {
+            let fee = amount
+                .checked_mul(u128::from(self.flash_fee_bps))
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?
+                / BPS_DENOMINATOR;
+
+            Ok(fee)
+        }
+
+        /// Mints `amount` to `receiver`, calls `FlashBorrower::on_flash_loan` on it and
+        /// then settles the loan.
+        ///
+        /// Settlement burns the principal from `receiver` and moves the fee to
+        /// `fee_recipient`, so the fee is existing value changing hands and total
+        /// supply ends where it started. Reverts with `Custom("flash loan active")` if
+        /// called from within a callback and with `Custom("flash loan not repaid")` if
+        /// `receiver` no longer holds `amount + fee`.
+        #[ink(message)]
+        pub fn flash_loan(
+            &mut self,
+            receiver: AccountId,
+            amount: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen_all()?;
+            Self::ensure_data_size(&data)?;
+
+            let fee = self.open_flash_loan(receiver, amount)?;
+
+            let callback_failed = || PSP22Error::Custom(String::from("flash loan callback failed"));
+            let mut borrower: ink::contract_ref!(FlashBorrower) = receiver.into();
+            borrower
+                .call_mut()
+                .on_flash_loan(self.env().caller(), amount, fee, data)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke()
+                .map_err(|_| callback_failed())?
+                .map_err(|_| callback_failed())??;
+
+            self.settle_flash_loan(receiver, amount, fee)
+        }
+
+        /// Returns whether the transfer fee is deducted from or added to `value`.
+        #[ink(message)]
+        pub fn fee_mode(&self) -> FeeMode {
+            self.fee_mode
+        }
+
+        /// Sets whether the transfer fee is deducted from or added to `value`.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_fee_mode(&mut self, fee_mode: FeeMode) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_fee_mode"));
+
+            self.fee_mode = fee_mode;
+
+            Ok(())
+        }
+
+        /// Returns `true` if transfers from or to `account` are not charged a fee.
+        ///
+        /// The fee recipient is always exempt.
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, account: AccountId) -> bool {
+            account == self.fee_recipient || self.fee_exempt.get(account).unwrap_or(false)
+        }
+
+        /// Adds or removes `account` from the fee-exempt list.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_fee_exempt(
+            &mut self,
+            account: AccountId,
+            exempt: bool,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_fee_exempt"));
+
+            if exempt {
+                self.fee_exempt.insert(account, &true);
+            } else {
+                self.fee_exempt.remove(account);
+            }
+
+            Ok(())
+        }
+
+        /// Returns the output amount of a constant-product (`x * y = k`) swap of
+        /// `amount_in` against the given reserves, after deducting `swap_fee_bps`.
+        ///
+        /// This is the shared math for pair contracts; no liquidity is held here.
+        ///
+        /// # Errors
+        ///
+        /// Returns `Custom("insufficient input amount")` if `amount_in` is zero,
+        /// `Custom("insufficient liquidity")` if either reserve is zero and
+        /// `Custom("overflow")` if an intermediate product overflows.
+        #[ink(message)]
+        pub fn get_amount_out(
+            &self,
+            amount_in: Balance,
+            reserve_in: Balance,
+            reserve_out: Balance,
+        ) -> Result<Balance, PSP22Error> {
+            if amount_in == 0 {
+                return Err(PSP22Error::Custom(String::from(
+                    "insufficient input amount",
+                )));
+            }
+
+            if reserve_in == 0 || reserve_out == 0 {
+                return Err(PSP22Error::Custom(String::from("insufficient liquidity")));
+            }
+
+            let overflow = || PSP22Error::Custom(String::from("overflow"));
+            let amount_in_with_fee = amount_in
+                .checked_mul(BPS_DENOMINATOR - u128::from(self.swap_fee_bps))
+                .ok_or_else(overflow)?;
+            let numerator = amount_in_with_fee
+                .checked_mul(reserve_out)
+                .ok_or_else(overflow)?;
+            let denominator = reserve_in
+                .checked_mul(BPS_DENOMINATOR)
+                .and_then(|reserve| reserve.checked_add(amount_in_with_fee))
+                .ok_or_else(overflow)?;
+
+            Ok(numerator / denominator)
+        }
+
+        /// Returns the total amount ever transferred from `from` to `to`, gross of
+        /// transfer fees: everything debited from `from`, including the fee in both
+        /// fee modes.
+        #[ink(message)]
+        pub fn total_transferred(&self, from: AccountId, to: AccountId) -> Balance {
+            self.transferred_between.get((from, to)).unwrap_or(0)
+        }
+
+        /// Mints `total_new` tokens split across `recipients` in proportion to `weights`.
+        ///
+        /// Each recipient gets `total_new * weight / sum(weights)`. The rounding remainder
+        /// goes to the last recipient, so exactly `total_new` is minted. Only callable by
+        /// the admin. A `Transfer` event from `None` is emitted per recipient.
+        #[ink(message)]
+        pub fn mint_proportional(
+            &mut self,
+            recipients: Vec<AccountId>,
+            weights: Vec<u32>,
+            total_new: Balance,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("mint_proportional"));
+
+            if recipients.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            if recipients.is_empty() {
+                return Err(PSP22Error::Custom(String::from("empty recipients")));
+            }
+
+            if recipients.len() != weights.len() {
+                return Err(PSP22Error::Custom(String::from("length mismatch")));
+            }
+
+            let weight_sum: u128 = weights.iter().map(|weight| u128::from(*weight)).sum();
+            if weight_sum == 0 {
+                return Err(PSP22Error::Custom(String::from("zero weights")));
+            }
+
+            let mut shares = Vec::with_capacity(recipients.len());
+            let mut distributed: Balance = 0;
+            for weight in &weights[..weights.len() - 1] {
+                let share = total_new
+                    .checked_mul(u128::from(*weight))
+                    .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?
+                    / weight_sum;
+                distributed += share;
+                shares.push(share);
+            }
+            shares.push(total_new - distributed);
+
+            for (recipient, share) in recipients.into_iter().zip(shares) {
+                self.mint_to(recipient, share)?;
+            }
+
+            Ok(())
+        }
+
+        /// Returns `true` if `account` has ever held a nonzero balance, even if it
+        /// holds nothing now.
+        #[ink(message)]
+        pub fn has_ever_held(&self, account: AccountId) -> bool {
+            self.ever_held.get(account).unwrap_or(false)
+        }
+
+        /// Returns the stream with id `stream_id`, if it is still active.
+        #[ink(message)]
+        pub fn stream(&self, stream_id: u32) -> Option<Stream> {
+            self.streams.get(stream_id)
+        }
+
+        /// Returns the amount of stream `stream_id` the recipient can withdraw now.
+        #[ink(message)]
+        pub fn stream_withdrawable(&self, stream_id: u32) -> Balance {
+            self.streams
+                .get(stream_id)
+                .map(|stream| self.stream_accrued(&stream) - stream.withdrawn)
+                .unwrap_or(0)
+        }
+
+        /// Escrows `deposit` tokens from the caller and streams them to `to` at
+        /// `rate_per_sec`, starting now. Returns the new stream id.
+        ///
+        /// A `Transfer` into escrow and a `StreamCreated` event are emitted.
+        #[ink(message)]
+        pub fn create_stream(
+            &mut self,
+            to: AccountId,
+            rate_per_sec: Balance,
+            deposit: Balance,
+        ) -> Result<u32, PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let sender = self.env().caller();
+
+            if to == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            if rate_per_sec == 0 || deposit == 0 || to == sender {
+                return Err(PSP22Error::Custom(String::from("invalid stream")));
+            }
+
+            // Escrowing into a stream would otherwise sidestep the lockup and the
+            // daily limit.
+            self.ensure_not_frozen_all()?;
+            self.ensure_transfer_allowed(sender)?;
+            self.ensure_kyc(&[sender, to])?;
+            self.record_daily_volume(sender, deposit)?;
+
+            let stream_id = self.next_stream_id;
+            self.next_stream_id = stream_id
+                .checked_add(1)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+
+            self.move_balance(sender, self.env().account_id(), deposit)?;
+            self.streams.insert(
+                stream_id,
+                &Stream {
+                    sender,
+                    recipient: to,
+                    rate_per_sec,
+                    deposit,
+                    withdrawn: 0,
+                    start: self.env().block_timestamp(),
+                },
+            );
+
+            self.env().emit_event(StreamCreated {
+                stream_id,
+                sender,
+                recipient: to,
+                rate_per_sec,
+                deposit,
+            });
+
+            Ok(stream_id)
+        }
+
+        /// Pays the recipient of stream `stream_id` everything accrued so far and
+        /// returns the amount paid. A fully drained stream is removed.
+        ///
+        /// Only callable by the stream recipient.
+        #[ink(message)]
+        pub fn withdraw_stream(&mut self, stream_id: u32) -> Result<Balance, PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let mut stream = self
+                .streams
+                .get(stream_id)
+                .ok_or_else(|| PSP22Error::Custom(String::from("stream not found")))?;
+
+            if self.env().caller() != stream.recipient {
+                return Err(PSP22Error::Custom(String::from("not stream recipient")));
+            }
+
+            let value = self.stream_accrued(&stream) - stream.withdrawn;
+            self.move_balance(self.env().account_id(), stream.recipient, value)?;
+            stream.withdrawn += value;
+
+            if stream.withdrawn == stream.deposit {
+                self.streams.remove(stream_id);
+            } else {
+                self.streams.insert(stream_id, &stream);
+            }
+
+            self.env().emit_event(StreamWithdrawn { stream_id, value });
+
+            Ok(value)
+        }
+
+        /// Returns the vesting schedule of `beneficiary`, if any, with `total` and
+        /// `released` in shares.
+        #[ink(message)]
+        pub fn vesting_schedule(&self, beneficiary: AccountId) -> Option<VestingSchedule> {
+            self.vesting.get(beneficiary)
+        }
+
+        /// Returns the vested tokens `beneficiary` can release now.
+        #[ink(message)]
+        pub fn releasable(&self, beneficiary: AccountId) -> Balance {
+            self.vesting
+                .get(beneficiary)
+                .map(|schedule| self.to_amount(self.vested(&schedule) - schedule.released))
+                .unwrap_or(0)
+        }
+
+        /// Mints `total` into escrow and vests it to `beneficiary` over `duration`
+        /// milliseconds from now, releasing nothing until `cliff` has passed.
+        ///
+        /// Reverts with `Custom("schedule exists")` while `beneficiary` has a
+        /// schedule, and with `Custom("invalid schedule")` for a zero `total` or
+        /// `duration` or a `cliff` past `duration`. Only callable by the admin. A
+        /// `Transfer` into escrow and a `VestingCreated` event are emitted.
+        #[ink(message)]
+        pub fn mint_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            total: Balance,
+            cliff: Timestamp,
+            duration: Timestamp,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("mint_vesting"));
+
+            if beneficiary == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            if self.vesting.contains(beneficiary) {
+                return Err(PSP22Error::Custom(String::from("schedule exists")));
+            }
+
+            if total == 0 || duration == 0 || cliff > duration {
+                return Err(PSP22Error::Custom(String::from("invalid schedule")));
+            }
+
+            let shares = self.shares_for(total)?;
+            self.mint_to(self.env().account_id(), total)?;
+            self.vesting.insert(
+                beneficiary,
+                &VestingSchedule {
+                    total: shares,
+                    released: 0,
+                    start: self.env().block_timestamp(),
+                    cliff,
+                    duration,
+                },
+            );
+
+            self.env().emit_event(VestingCreated {
+                beneficiary,
+                total: self.to_amount(shares),
+                cliff,
+                duration,
+            });
+
+            Ok(())
+        }
+
+        /// Pays the caller everything vested but not yet released and returns the
+        /// amount paid. A fully released schedule is removed.
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<Balance, PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let beneficiary = self.env().caller();
+            let mut schedule = self
+                .vesting
+                .get(beneficiary)
+                .ok_or_else(|| PSP22Error::Custom(String::from("no schedule")))?;
+
+            let shares = self.vested(&schedule) - schedule.released;
+            self.move_shares(self.env().account_id(), beneficiary, shares)?;
+            schedule.released += shares;
+            let value = self.to_amount(shares);
+
+            if schedule.released == schedule.total {
+                self.vesting.remove(beneficiary);
+            } else {
+                self.vesting.insert(beneficiary, &schedule);
+            }
+
+            self.env()
+                .emit_event(VestingReleased { beneficiary, value });
+
+            Ok(value)
+        }
+
+        /// Cancels stream `stream_id`, paying the recipient what has accrued and
+        /// refunding the unspent remainder to the sender.
+        ///
+        /// Only callable by the stream sender.
+        #[ink(message)]
+        pub fn cancel_stream(&mut self, stream_id: u32) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let stream = self
+                .streams
+                .get(stream_id)
+                .ok_or_else(|| PSP22Error::Custom(String::from("stream not found")))?;
+
+            if self.env().caller() != stream.sender {
+                return Err(PSP22Error::Custom(String::from("not stream sender")));
+            }
+
+            let accrued = self.stream_accrued(&stream);
+            let recipient_payout = accrued - stream.withdrawn;
+            let sender_refund = stream.deposit - accrued;
+            let escrow = self.env().account_id();
+
+            self.streams.remove(stream_id);
+            self.move_balance(escrow, stream.recipient, recipient_payout)?;
+            self.move_balance(escrow, stream.sender, sender_refund)?;
+
+            self.env().emit_event(StreamCancelled {
+                stream_id,
+                recipient_payout,
+                sender_refund,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the sub-treasury account labelled `tag`.
+        ///
+        /// The account is derived by hashing the contract address with the tag, so the
+        /// same tag always maps to the same account and no one holds its key.
+        #[ink(message)]
+        pub fn derive_treasury(&self, tag: [u8; 8]) -> AccountId {
+            let mut input = Vec::with_capacity(8 + 32 + 8);
+            input.extend_from_slice(b"treasury");
+            input.extend_from_slice(self.env().account_id().as_ref());
+            input.extend_from_slice(&tag);
+
+            AccountId::from(self.env().hash_bytes::<ink::env::hash::Blake2x256>(&input))
+        }
+
+        /// Returns the sub-tokens deployed by `instantiate_child`, oldest first.
+        #[ink(message)]
+        pub fn children(&self) -> Vec<AccountId> {
+            (0..self.child_count)
+                .filter_map(|index| self.children.get(index))
+                .collect()
+        }
+
+        /// Deploys a per-property sub-token from the uploaded RED code `code_hash`
+        /// and records it in `children`.
+        ///
+        /// The child is an independent token administered by this token's admin. Its
+        /// address depends only on this contract, `code_hash`, the constructor input
+        /// and `salt`, so one salt per property gives a predictable address. Reverts
+        /// with `Custom("instantiation failed")` if the deployment fails. Only
+        /// callable by the admin.
+        #[ink(message)]
+        pub fn instantiate_child(
+            &mut self,
+            code_hash: Hash,
+            init_supply: Balance,
+            decimals: u8,
+            salt: [u8; 32],
+        ) -> Result<AccountId, PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("instantiate_child"));
+
+            let failed = || PSP22Error::Custom(String::from("instantiation failed"));
+            let child = RedTokenRef::new(init_supply, self.admin, decimals)
+                .code_hash(code_hash)
+                .endowment(0)
+                .salt_bytes(salt)
+                .try_instantiate()
+                .map_err(|_| failed())?
+                .map_err(|_| failed())?;
+            let child = ink::ToAccountId::<Environment>::to_account_id(&child);
+
+            let index = self.child_count;
+            self.child_count = index
+                .checked_add(1)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+            self.children.insert(index, &child);
+
+            Ok(child)
+        }
+
+        /// Mints `value` new tokens to the sub-treasury labelled `tag`.
+        ///
+        /// Only callable by the admin. A `Transfer` event from `None` is emitted.
+        #[ink(message)]
+        pub fn mint_to_treasury(&mut self, tag: [u8; 8], value: Balance) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("mint_to_treasury"));
+
+            let treasury = self.derive_treasury(tag);
+            self.mint_to(treasury, value)
+        }
+
+        /// Returns the root of the airdrop merkle tree, if one is set.
+        #[ink(message)]
+        pub fn merkle_root(&self) -> Option<[u8; 32]> {
+            self.merkle_root
+        }
+
+        /// Sets the root of the airdrop merkle tree.
+        ///
+        /// Leaves are `blake2x256(account ++ amount_le)` and inner nodes hash their
+        /// children in sorted order. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_merkle_root(&mut self, root: [u8; 32]) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_merkle_root"));
+
+            self.merkle_root = Some(root);
+
+            Ok(())
+        }
+
+        /// Returns `true` if the airdrop of `amount` to `account` has been claimed.
+        #[ink(message)]
+        pub fn is_merkle_claimed(&self, account: AccountId, amount: Balance) -> bool {
+            self.merkle_claimed
+                .contains(Self::merkle_leaf(account, amount))
+        }
+
+        /// Mints the caller's airdrop of `amount` after verifying `proof` against the
+        /// stored merkle root. Each leaf can be claimed once.
+        ///
+        /// A `Transfer` event from `None` is emitted.
+        #[ink(message)]
+        pub fn claim_merkle(
+            &mut self,
+            amount: Balance,
+            proof: Vec<[u8; 32]>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let root = self
+                .merkle_root
+                .ok_or_else(|| PSP22Error::Custom(String::from("no merkle root")))?;
+
+            if proof.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            let leaf = Self::merkle_leaf(caller, amount);
+
+            if self.merkle_claimed.contains(leaf) {
+                return Err(PSP22Error::Custom(String::from("already claimed")));
+            }
+
+            let computed = proof
+                .iter()
+                .fold(leaf, |node, sibling| Self::merkle_parent(&node, sibling));
+
+            if computed != root {
+                return Err(PSP22Error::Custom(String::from("invalid proof")));
+            }
+
+            self.merkle_claimed.insert(leaf, &true);
+            self.mint_to(caller, amount)
+        }
+
+        /// Returns the RED held by the contract's own account, e.g. in escrow.
+        #[ink(message)]
+        pub fn contract_balance(&self) -> Balance {
+            self.balance_of(self.env().account_id())
+        }
+
+        /// Returns the native currency held as backing for the supply.
+        #[ink(message)]
+        pub fn native_reserve(&self) -> Balance {
+            self.native_reserve
+        }
+
+        /// Adds the transferred native value to the backing reserve.
+        ///
+        /// Only callable by the admin.
+        #[ink(message, payable)]
+        pub fn fund_reserve(&mut self) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("fund_reserve"));
+
+            self.native_reserve = self
+                .native_reserve
+                .checked_add(self.env().transferred_value())
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+
+            Ok(())
+        }
+
+        /// Mints `value` to `to` for the transfer `src_tx` from `src_chain_id`.
+        ///
+        /// Only callable by holders of `BRIDGE_ROLE`; others get
+        /// `Custom("not bridge")`. Each source transaction mints once, a replay
+        /// reverts with `Custom("already processed")`. `Transfer` and `BridgeMint`
+        /// events are emitted.
+        #[ink(message)]
+        pub fn bridge_mint(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            src_chain_id: u32,
+            src_tx: [u8; 32],
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            self.ensure_bridge()?;
+
+            if self.bridge_processed.contains((src_chain_id, src_tx)) {
+                return Err(PSP22Error::Custom(String::from("already processed")));
+            }
+
+            self.mint_to(to, value)?;
+            self.bridge_processed.insert((src_chain_id, src_tx), &true);
+
+            self.env().emit_event(BridgeMint {
+                to,
+                value,
+                src_chain_id,
+                src_tx,
+            });
+
+            Ok(())
+        }
+
+        /// Burns `value` from `from` so the bridge can release it on `dst_chain_id`.
+        ///
+        /// Only callable by holders of `BRIDGE_ROLE`, who act for the account being
+        /// bridged out and need no allowance. `Transfer` and `BridgeBurn` events are
+        /// emitted.
+        #[ink(message)]
+        pub fn bridge_burn(
+            &mut self,
+            from: AccountId,
+            value: Balance,
+            dst_chain_id: u32,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            self.ensure_bridge()?;
+
+            if from == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroSenderAddress);
+            }
+
+            let shares = self.shares_of(from);
+
+            if self.to_amount(shares) < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            let value = self.burn_from(from, shares, value)?;
+            self.emit_transfer(Some(from), None, value, Vec::new());
+            self.env().emit_event(BridgeBurn {
+                from,
+                value,
+                dst_chain_id,
+            });
+
+            Ok(())
+        }
+
+        /// Burns `value` of the caller's tokens with `BurnReason::Generic`.
+        ///
+        /// See `burn_with_reason`.
+        #[ink(message)]
+        pub fn burn(&mut self, value: Balance) -> Result<(), PSP22Error> {
+            self.burn_with_reason(value, BurnReason::Generic as u8)
+        }
+
+        /// Burns `value` of the caller's tokens, recording why in `reason_code`, a
+        /// `BurnReason` code.
+        ///
+        /// A `Transfer` event to `None` and a `Burned` event are emitted.
+        ///
+        /// Reverts with `Custom("unknown burn reason")` if `reason_code` is not a
+        /// `BurnReason`.
+        #[ink(message)]
+        pub fn burn_with_reason(
+            &mut self,
+            value: Balance,
+            reason_code: u8,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            BurnReason::try_from(reason_code)?;
+            let caller = self.env().caller();
+
+            if self.is_frozen(caller) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            let shares = self.shares_of(caller);
+
+            if self.to_amount(shares) < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            let value = self.burn_from(caller, shares, value)?;
+            self.emit_transfer(Some(caller), None, value, Vec::new());
+            self.env().emit_event(Burned {
+                from: caller,
+                value,
+                reason_code,
+            });
+
+            Ok(())
+        }
+
+        /// Burns `amount` of the caller's tokens and pays out the proportional share
+        /// of the native reserve, `amount * native_reserve / total_supply`.
+        ///
+        /// Storage is updated before the native transfer, so a failed payout reverts
+        /// the whole call. Returns the native amount paid.
+        ///
+        /// A `Transfer` event to `None` is emitted.
+        #[ink(message)]
+        pub fn burn_and_redeem(&mut self, amount: Balance) -> Result<Balance, PSP22Error> {
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+
+            if self.is_frozen(caller) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            let shares = self.shares_of(caller);
+
+            if self.to_amount(shares) < amount {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            if amount == 0 {
+                return Ok(0);
+            }
+
+            // Priced on what is actually burned, which a rebase can round down.
+            let amount = self.to_amount(self.shares_out(shares, amount)?);
+            let payout = amount
+                .checked_mul(self.native_reserve)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?
+                .checked_div(self.total_supply())
+                .ok_or_else(|| PSP22Error::Custom(String::from("no supply")))?;
+            let native_reserve = self
+                .native_reserve
+                .checked_sub(payout)
+                .ok_or_else(|| PSP22Error::Custom(String::from("reserve underflow")))?;
+
+            self.burn_from(caller, shares, amount)?;
+            self.native_reserve = native_reserve;
+
+            self.emit_transfer(Some(caller), None, amount, Vec::new());
+
+            if payout > 0 {
+                self.env()
+                    .transfer(caller, payout)
+                    .map_err(|_| PSP22Error::Custom(String::from("native transfer failed")))?;
+            }
+
+            Ok(payout)
+        }
+
+        /// Returns the proposed admin if the proposal has not yet expired.
+        #[ink(message)]
+        pub fn pending_admin(&self) -> Option<AccountId> {
+            if self.env().block_timestamp() > self.pending_admin_expires_at {
+                return None;
+            }
+
+            self.pending_admin
+        }
+
+        /// Returns the seconds a proposed admin has to accept.
+        #[ink(message)]
+        pub fn admin_transfer_grace_secs(&self) -> u64 {
+            self.admin_transfer_grace_secs
+        }
+
+        /// Sets the acceptance window for future admin proposals. Only callable by
+        /// the admin; a proposal already pending keeps its deadline.
+        #[ink(message)]
+        pub fn set_admin_transfer_grace_secs(&mut self, secs: u64) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_admin_transfer_grace_secs"));
+
+            self.admin_transfer_grace_secs = secs;
+            Ok(())
+        }
+
+        /// Proposes `new_admin` as the successor admin, replacing any earlier proposal.
+        ///
+        /// `new_admin` must call `accept_admin` within `admin_transfer_grace_secs`.
+        /// Only callable by the admin. An `AdminTransferProposed` event is emitted.
+        #[ink(message)]
+        pub fn propose_admin(&mut self, new_admin: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("propose_admin"));
+
+            if new_admin == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            // Block timestamps are in milliseconds.
+            let expires_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(self.admin_transfer_grace_secs.saturating_mul(1_000));
+            self.pending_admin = Some(new_admin);
+            self.pending_admin_expires_at = expires_at;
+
+            self.env().emit_event(AdminTransferProposed {
+                new_admin,
+                expires_at,
+            });
+
+            Ok(())
+        }
+
+        /// Accepts a pending admin proposal made for the caller.
+        ///
+        /// The KYC admin and guardian roles move along unless they were handed to
+        /// another account, so the previous admin keeps no way to `pause`.
+        /// Reverts with `Custom("transfer expired")` once the grace period has
+        /// lapsed. An `AdminChanged` event is emitted.
+        #[ink(message)]
+        pub fn accept_admin(&mut self) -> Result<(), PSP22Error> {
+            let caller = self.env().caller();
+
+            if self.pending_admin != Some(caller) {
+                return Err(PSP22Error::Custom(String::from("not pending admin")));
+            }
+
+            if self.env().block_timestamp() > self.pending_admin_expires_at {
+                return Err(PSP22Error::Custom(String::from("transfer expired")));
+            }
+
+            let old_admin = self.admin;
+            self.admin = caller;
+            self.pending_admin = None;
+
+            // Roles the previous admin still holds by default move with it.
+            if self.kyc_admin == old_admin {
+                self.kyc_admin = caller;
+            }
+
+            if self.guardian == old_admin {
+                self.guardian = caller;
+            }
+
+            self.env().emit_event(AdminChanged {
+                old_admin,
+                new_admin: caller,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the replacement scheduled for `old` and when it can be executed.
+        #[ink(message)]
+        pub fn pending_recovery(&self, old: AccountId) -> Option<(AccountId, Timestamp)> {
+            self.pending_recoveries.get(old)
+        }
+
+        /// Schedules moving the holdings of `old`, whose keys were lost, to `new`.
+        ///
+        /// `recover_account` can execute it once `RECOVERY_DELAY_MS` has passed,
+        /// leaving governance and the holder time to object. Scheduling again
+        /// replaces the pending entry and restarts the delay. Only callable by the
+        /// admin. A `RecoveryScheduled` event is emitted.
+        #[ink(message)]
+        pub fn schedule_recovery(
+            &mut self,
+            old: AccountId,
+            new: AccountId,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("schedule_recovery"));
+
+            if new == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            if old == new {
+                return Err(PSP22Error::Custom(String::from("same account")));
+            }
+
+            let ready_at = self
+                .env()
+                .block_timestamp()
+                .saturating_add(RECOVERY_DELAY_MS);
+            self.pending_recoveries.insert(old, &(new, ready_at));
+
+            self.env()
+                .emit_event(RecoveryScheduled { old, new, ready_at });
+
+            Ok(())
+        }
+
+        /// Cancels the recovery scheduled for `old`. Only callable by the admin.
+        #[ink(message)]
+        pub fn cancel_recovery(&mut self, old: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("cancel_recovery"));
+
+            self.pending_recoveries.remove(old);
+
+            Ok(())
+        }
+
+        /// Executes the recovery scheduled for `old`, moving its whole balance and
+        /// the allowances it granted to each of `spenders` over to `new`.
+        ///
+        /// Allowances can't be enumerated on-chain, so the spenders to migrate
+        /// are passed in; the rest stay with `old`. A migrated allowance is added to
+        /// whatever `new` already grants that spender, and `Approval` is emitted for
+        /// both owners. Reverts with
+        /// `Custom("no recovery scheduled")` unless a recovery to `new` is pending and
+        /// `Custom("recovery timelocked")` until its delay has passed. Only callable
+        /// by the admin. `Transfer` and `Recovered` events are emitted.
+        #[ink(message)]
+        pub fn recover_account(
+            &mut self,
+            old: AccountId,
+            new: AccountId,
+            spenders: Vec<AccountId>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("recover_account"));
+
+            if spenders.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            let ready_at = match self.pending_recoveries.get(old) {
+                Some((pending, ready_at)) if pending == new => ready_at,
+                _ => return Err(PSP22Error::Custom(String::from("no recovery scheduled"))),
+            };
+
+            if self.env().block_timestamp() < ready_at {
+                return Err(PSP22Error::Custom(String::from("recovery timelocked")));
+            }
+
+            if self.is_frozen(new) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            self.ensure_not_frozen_all()?;
+            self.ensure_kyc(&[new])?;
+            self.pending_recoveries.remove(old);
+
+            // Written directly rather than through `move_balance`: a lost account is
+            // often frozen, which must not block its recovery.
+            let shares = self.shares_of(old);
+            let previous = self.shares_of(new);
+            let new_shares = previous
+                .checked_add(shares)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+            self.write_shares(old, shares, 0);
+            self.write_shares(new, previous, new_shares);
+            let value = self.to_amount(shares);
+
+            for spender in spenders {
+                let allowance = self.allowance(old, spender);
+
+                if allowance == 0 || spender == new {
+                    continue;
+                }
+
+                let previous = self.allowance(new, spender);
+                let combined = previous
+                    .checked_add(allowance)
+                    .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+                self.allowances.remove((old, spender));
+                self.approval_log.remove((old, spender));
+                self.track_approval_count(old, allowance, 0)?;
+                self.env().emit_event(AllowanceChanged {
+                    owner: old,
+                    spender,
+                    old: allowance,
+                    new: 0,
+                });
+                self.env().emit_event(Approval {
+                    owner: old,
+                    spender,
+                    value: 0,
+                    data: Vec::new(),
+                });
+                self.set_approval(new, spender, previous, combined)?;
+                self.env().emit_event(Approval {
+                    owner: new,
+                    spender,
+                    value: combined,
+                    data: Vec::new(),
+                });
+            }
+
+            self.emit_transfer(Some(old), Some(new), value, Vec::new());
+            self.env().emit_event(Recovered { old, new, value });
+
+            Ok(())
+        }
+
+        /// Returns the account fees are paid to.
+        #[ink(message)]
+        pub fn fee_recipient(&self) -> AccountId {
+            self.fee_recipient
+        }
+
+        /// Returns the proposed fee recipient awaiting acceptance, if any.
+        #[ink(message)]
+        pub fn pending_fee_recipient(&self) -> Option<AccountId> {
+            self.pending_fee_recipient
+        }
+
+        /// Proposes `recipient` as the new fee recipient.
+        ///
+        /// The change only takes effect once `recipient` calls `accept_fee_recipient`,
+        /// so fees can't be redirected to an account nobody controls. Only callable by
+        /// the admin. A `FeeRecipientChangeProposed` event is emitted.
+        #[ink(message)]
+        pub fn propose_fee_recipient(&mut self, recipient: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("propose_fee_recipient"));
+
+            if recipient == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            self.pending_fee_recipient = Some(recipient);
+            self.env()
+                .emit_event(FeeRecipientChangeProposed { recipient });
+
+            Ok(())
+        }
+
+        /// Accepts a pending fee recipient proposal made for the caller.
+        ///
+        /// A `FeeRecipientChanged` event is emitted.
+        #[ink(message)]
+        pub fn accept_fee_recipient(&mut self) -> Result<(), PSP22Error> {
+            let caller = self.env().caller();
+
+            if self.pending_fee_recipient != Some(caller) {
+                return Err(PSP22Error::Custom(String::from(
+                    "not pending fee recipient",
+                )));
+            }
+
+            let old_recipient = self.fee_recipient;
+            self.fee_recipient = caller;
+            self.pending_fee_recipient = None;
+
+            self.env().emit_event(FeeRecipientChanged {
+                old_recipient,
+                new_recipient: caller,
+            });
+
+            Ok(())
+        }
+
+        /// Increases the caller's allowance for each of `spenders` by the matching entry
+        /// of `deltas`, as `increase_allowance` would.
+        ///
+        /// An `Approval` event is emitted per entry. If any entry fails the whole call
+        /// reverts. Batches longer than `MAX_BATCH_SIZE` are rejected.
+        #[ink(message)]
+        pub fn increase_allowance_batch(
+            &mut self,
+            spenders: Vec<AccountId>,
+            deltas: Vec<Balance>,
+        ) -> Result<(), PSP22Error> {
+            Self::ensure_batch_shape(spenders.len(), deltas.len())?;
+
+            for (spender, delta_value) in spenders.into_iter().zip(deltas) {
+                PSP22::increase_allowance(self, spender, delta_value)?;
+            }
+
+            Ok(())
+        }
+
+        /// Decreases the caller's allowance for each of `spenders` by the matching entry
+        /// of `deltas`, as `decrease_allowance` would.
+        ///
+        /// An `Approval` event is emitted per entry. If any entry fails, e.g. with
+        /// `InsufficientAllowance`, the whole call reverts. Batches longer than
+        /// `MAX_BATCH_SIZE` are rejected.
+        #[ink(message)]
+        pub fn decrease_allowance_batch(
+            &mut self,
+            spenders: Vec<AccountId>,
+            deltas: Vec<Balance>,
+        ) -> Result<(), PSP22Error> {
+            Self::ensure_batch_shape(spenders.len(), deltas.len())?;
+
+            for (spender, delta_value) in spenders.into_iter().zip(deltas) {
+                PSP22::decrease_allowance(self, spender, delta_value)?;
+            }
+
+            Ok(())
+        }
+
+        /// Returns the latest allowance `owner` granted `spender` through `approve`,
+        /// `increase_allowance` or `decrease_allowance`, with the block it was set in.
+        #[ink(message)]
+        pub fn last_approval(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<ApprovalRecord> {
+            self.approval_log.get((owner, spender))
+        }
+
+        /// Returns `true` if `operator` may move any amount of `owner`'s tokens.
+        #[ink(message)]
+        pub fn is_operator(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.operators.get((owner, operator)).unwrap_or(false)
+        }
+
+        /// Grants or revokes unlimited transfer authority over the caller's tokens.
+        ///
+        /// Operator rights are independent of the numeric allowance: `transfer_from`
+        /// by an operator neither requires nor consumes it.
+        ///
+        /// An `OperatorSet` event is emitted.
+        #[ink(message)]
+        pub fn set_operator(
+            &mut self,
+            operator: AccountId,
+            approved: bool,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+
+            if operator == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            self.ensure_not_self_approval(owner, operator)?;
+
+            if approved {
+                self.operators.insert((owner, operator), &true);
+            } else {
+                self.operators.remove((owner, operator));
+            }
+
+            self.env().emit_event(OperatorSet {
+                owner,
+                operator,
+                approved,
+            });
+
+            Ok(())
+        }
+
+        /// Returns the timestamp until which the lockup phase lasts.
+        #[ink(message)]
+        pub fn lockup_end(&self) -> Timestamp {
+            self.lockup_end
+        }
+
+        /// Sets the end of the lockup phase; `0` disables it.
+        ///
+        /// Until then only allowlisted accounts and the admin may send tokens. The
+        /// admin is always exempt so it can seed liquidity and make distributions,
+        /// to any recipient; holders must trust it not to move tokens out early.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_lockup_end(&mut self, lockup_end: Timestamp) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_lockup_end"));
+
+            self.lockup_end = lockup_end;
+
+            Ok(())
+        }
+
+        /// Returns `true` if `account` may send tokens during the lockup phase, as
+        /// the admin always may.
+        #[ink(message)]
+        pub fn is_transfer_allowed(&self, account: AccountId) -> bool {
+            account == self.admin || self.transfer_allowlist.get(account).unwrap_or(false)
+        }
+
+        /// Adds or removes `account` from the lockup allowlist.
+        ///
+        /// Only callable by the admin. A `TransferAllowlistUpdated` event is emitted.
+        #[ink(message)]
+        pub fn set_transfer_allowed(
+            &mut self,
+            account: AccountId,
+            allowed: bool,
+        ) -> Result<(), PSP22Error> {
+            self.set_transfer_allowed_batch(vec![account], allowed)
+        }
+
+        /// Adds or removes every account in `accounts` from the lockup allowlist.
+        ///
+        /// Only callable by the admin. A `TransferAllowlistUpdated` event is emitted
+        /// for each account. Batches longer than `MAX_BATCH_SIZE` are rejected.
+        #[ink(message)]
+        pub fn set_transfer_allowed_batch(
+            &mut self,
+            accounts: Vec<AccountId>,
+            allowed: bool,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_transfer_allowed_batch"));
+
+            if accounts.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            for account in accounts {
+                if allowed {
+                    self.transfer_allowlist.insert(account, &true);
+                } else {
+                    self.transfer_allowlist.remove(account);
+                }
+
+                self.env()
+                    .emit_event(TransferAllowlistUpdated { account, allowed });
+            }
+
+            Ok(())
+        }
+
+        /// Returns `true` if transfers and mints require KYC-verified accounts.
+        #[ink(message)]
+        pub fn kyc_required(&self) -> bool {
+            self.kyc_required
+        }
+
+        /// Sets whether both sides of `transfer` and `transfer_from`, and the
+        /// recipient of a mint, must be KYC-verified. Non-permissioned deployments
+        /// leave this off. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_kyc_required(&mut self, required: bool) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_kyc_required"));
+
+            self.kyc_required = required;
+
+            Ok(())
+        }
+
+        /// Returns the account managing KYC verification.
+        #[ink(message)]
+        pub fn kyc_admin(&self) -> AccountId {
+            self.kyc_admin
+        }
+
+        /// Hands KYC verification over to `kyc_admin`. Only callable by the admin.
+        #[ink(message)]
+        pub fn set_kyc_admin(&mut self, kyc_admin: AccountId) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_kyc_admin"));
+
+            self.kyc_admin = kyc_admin;
+
+            Ok(())
+        }
+
+        /// Returns `true` if `account` passed KYC.
+        #[ink(message)]
+        pub fn is_kyc_verified(&self, account: AccountId) -> bool {
+            self.kyc_verified.get(account).unwrap_or(false)
+        }
+
+        /// Marks `account` as KYC-verified or not. Only callable by the KYC admin;
+        /// others get `Custom("not kyc admin")`.
+        #[ink(message)]
+        pub fn set_kyc_verified(
+            &mut self,
+            account: AccountId,
+            verified: bool,
+        ) -> Result<(), PSP22Error> {
+            if self.env().caller() != self.kyc_admin {
+                return Err(PSP22Error::Custom(String::from("not kyc admin")));
+            }
+
+            if verified {
+                self.kyc_verified.insert(account, &true);
+            } else {
+                self.kyc_verified.remove(account);
+            }
+
+            Ok(())
+        }
+
+        /// Returns `true` if the contract `account` may receive tokens.
+        #[ink(message)]
+        pub fn is_recipient_contract_allowed(&self, account: AccountId) -> bool {
+            self.recipient_contract_allowlist
+                .get(account)
+                .unwrap_or(false)
+        }
+
+        /// Allows or forbids the contract `account`, e.g. a DEX pair or vault, to
+        /// receive tokens. Other contracts are rejected with `SafeTransferCheckFailed`;
+        /// allowed ones must still accept each transfer through `PSP22Receiver`.
+        ///
+        /// Only callable by the admin.
+        #[ink(message)]
+        pub fn set_recipient_contract_allowed(
+            &mut self,
+            account: AccountId,
+            allowed: bool,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_admin()?;
+            self.record_admin_action(ink::selector_bytes!("set_recipient_contract_allowed"));
+
+            if allowed {
+                self.recipient_contract_allowlist.insert(account, &true);
+            } else {
+                self.recipient_contract_allowlist.remove(account);
+            }
+
+            Ok(())
+        }
+
+        /// Returns the balance, freeze and lockup state of `who` in one read.
+        #[ink(message)]
+        pub fn account_info(&self, who: AccountId) -> AccountInfo {
+            let locked =
+                self.env().block_timestamp() < self.lockup_end && !self.is_transfer_allowed(who);
+
+            AccountInfo {
+                balance: self.balance_of(who),
+                staked: 0,
+                claimable_income: 0,
+                frozen: self.is_frozen(who),
+                locked_until: if locked { self.lockup_end } else { 0 },
+            }
+        }
+
+        /// Returns `true` if `account` is frozen.
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.get(account).unwrap_or(false)
+        }
+
+        /// Freezes every account in `accounts`, blocking transfers from and to them.
+        ///
+        /// Only callable by holders of `FREEZER_ROLE`; others get `Custom("not freezer")`.
+        /// A `Frozen` event is emitted for each account. Batches longer than
+        /// `MAX_BATCH_SIZE` are rejected.
+        #[ink(message)]
+        pub fn freeze_batch(&mut self, accounts: Vec<AccountId>) -> Result<(), PSP22Error> {
+            self.ensure_freezer()?;
+
+            if accounts.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            for account in accounts {
+                self.frozen.insert(account, &true);
+                self.env().emit_event(Frozen { account });
+            }
+
+            Ok(())
+        }
+
+        /// Unfreezes every account in `accounts`.
+        ///
+        /// Only callable by holders of `FREEZER_ROLE`; others get `Custom("not freezer")`.
+        /// An `Unfrozen` event is emitted for each account. Batches longer than
+        /// `MAX_BATCH_SIZE` are rejected.
+        #[ink(message)]
+        pub fn unfreeze_batch(&mut self, accounts: Vec<AccountId>) -> Result<(), PSP22Error> {
+            self.ensure_freezer()?;
+
+            if accounts.len() > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            for account in accounts {
+                self.frozen.remove(account);
+                self.env().emit_event(Unfrozen { account });
+            }
+
+            Ok(())
+        }
+    }
+
+    impl RedToken {
+        /// Rejects callers without `FREEZER_ROLE` with `Custom("not freezer")`.
+        fn ensure_freezer(&self) -> PSP22Result<()> {
+            if !self.has_role(FREEZER_ROLE, self.env().caller()) {
+                return Err(PSP22Error::Custom(String::from("not freezer")));
+            }
+
+            Ok(())
+        }
+
+        /// Rejects callers without `BRIDGE_ROLE` with `Custom("not bridge")`.
+        fn ensure_bridge(&self) -> PSP22Result<()> {
+            if !self.has_role(BRIDGE_ROLE, self.env().caller()) {
+                return Err(PSP22Error::Custom(String::from("not bridge")));
+            }
+
+            Ok(())
+        }
+
+        /// Rejects callers other than the admin with `Custom("not admin")`.
+        fn ensure_admin(&self) -> PSP22Result<()> {
+            if self.env().caller() != self.admin {
+                return Err(PSP22Error::Custom(String::from("not admin")));
+            }
+
+            Ok(())
+        }
+
+        /// Counts an admin-gated message and emits `AdminAction` for it.
+        ///
+        /// A message that fails after this point reverts, taking the event and the
+        /// count with it, so only executed actions are recorded.
+        fn record_admin_action(&mut self, action_code: [u8; 4]) {
+            self.admin_action_count += 1;
+            self.env().emit_event(AdminAction {
+                action_code,
+                caller: self.env().caller(),
+                block: self.env().block_number(),
+            });
+        }
+
+        /// Rejects paired batch arguments that differ in length or exceed `MAX_BATCH_SIZE`.
+        fn ensure_batch_shape(len: usize, other_len: usize) -> PSP22Result<()> {
+            if len > MAX_BATCH_SIZE {
+                return Err(PSP22Error::Custom(String::from("batch too large")));
+            }
+
+            if len != other_len {
+                return Err(PSP22Error::Custom(String::from("length mismatch")));
+            }
+
+            Ok(())
+        }
+
+        /// Returns `Custom("data too large")` if `data` exceeds `MAX_DATA_BYTES`.
+        fn ensure_data_size(data: &[u8]) -> PSP22Result<()> {
+            if data.len() > MAX_DATA_BYTES {
+                return Err(PSP22Error::Custom(String::from("data too large")));
+            }
+
+            Ok(())
+        }
+
+        /// Returns `Custom("paused")` while the contract is paused.
+        ///
+        /// Only call this from mutating messages; getters must keep working while paused
+        /// so explorers and wallets can still read state during an incident.
+        fn ensure_not_paused(&self) -> PSP22Result<()> {
+            if self.paused() {
+                return Err(PSP22Error::Custom(String::from("paused")));
+            }
+
+            Ok(())
+        }
+
+        /// Asks a contract `to` to accept `value` through
+        /// `PSP22Receiver::before_received`, rejecting the transfer with
+        /// `SafeTransferCheckFailed` if the call fails or errors.
+        ///
+        /// Called once balances are written; a rejection reverts them with the call.
+        fn ensure_receiver_accepts(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: &[u8],
+        ) -> Result<(), PSP22Error> {
+            if !self.env().is_contract(&to) {
+                return Ok(());
+            }
+
+            let rejected = || {
+                PSP22Error::SafeTransferCheckFailed(format!(
+                    "AccountId {:?} rejected the transfer",
+                    &to
+                ))
+            };
+
+            let mut receiver: ink::contract_ref!(PSP22Receiver) = to.into();
+            receiver
+                .call_mut()
+                .before_received(operator, from, value, data.to_vec())
+                .try_invoke()
+                .map_err(|_| rejected())?
+                .map_err(|_| rejected())?
+                .map_err(|_| rejected())
+        }
+
+        /// Rejects transfers with `Custom("all transfers frozen")` during a `freeze_all`.
+        fn ensure_not_frozen_all(&self) -> PSP22Result<()> {
+            if self.freeze_reason.is_some() {
+                return Err(PSP22Error::Custom(String::from("all transfers frozen")));
+            }
+
+            Ok(())
+        }
+
+        /// Rejects transfers from accounts not on the allowlist while the lockup
+        /// phase lasts.
+        fn ensure_transfer_allowed(&self, from: AccountId) -> PSP22Result<()> {
+            if self.env().block_timestamp() < self.lockup_end && !self.is_transfer_allowed(from) {
+                return Err(PSP22Error::Custom(String::from("locked up")));
+            }
+
+            Ok(())
+        }
+
+        /// Rejects a `value` with a fractional display part while whole units are
+        /// required.
+        fn ensure_whole_units(&self, value: Balance) -> PSP22Result<()> {
+            if self.require_whole_units && self.format_amount(value).1 != 0 {
+                return Err(PSP22Error::Custom(String::from("whole units only")));
+            }
+
+            Ok(())
+        }
+
+        /// Rejects unverified `accounts` with `Custom("kyc required")` while KYC is
+        /// required. The zero address, i.e. a burn, and the contract's own escrow
+        /// account are not checked.
+        fn ensure_kyc(&self, accounts: &[AccountId]) -> PSP22Result<()> {
+            if !self.kyc_required {
+                return Ok(());
+            }
+
+            let exempt = [AccountId::from([0u8; 32]), self.env().account_id()];
+
+            for account in accounts {
+                if !exempt.contains(account) && !self.is_kyc_verified(*account) {
+                    return Err(PSP22Error::Custom(String::from("kyc required")));
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Converts `value` from `from` decimals to `to` decimals, truncating when
+        /// scaling down.
+        fn rescale(value: Balance, from: u8, to: u8) -> PSP22Result<Balance> {
+            let overflow = || PSP22Error::Custom(String::from("overflow"));
+
+            if to >= from {
+                let factor = 10u128
+                    .checked_pow(u32::from(to - from))
+                    .ok_or_else(overflow)?;
+
+                return value.checked_mul(factor).ok_or_else(overflow);
+            }
+
+            // A divisor beyond `Balance::MAX` leaves nothing of any value.
+            Ok(10u128
+                .checked_pow(u32::from(from - to))
+                .map_or(0, |factor| value / factor))
+        }
+
+        /// Returns the greatest common divisor of `a` and `b`.
+        fn gcd(mut a: u128, mut b: u128) -> u128 {
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+
+            a
+        }
+
+        /// Returns `value * num / den` rounded down, saturating instead of overflowing.
+        fn mul_div(value: u128, num: u128, den: u128) -> u128 {
+            // Split so the intermediate product stays small for ordinary factors.
+            (value / den)
+                .saturating_mul(num)
+                .saturating_add((value % den).saturating_mul(num) / den)
+        }
+
+        /// Converts a token amount to shares, rounding down.
+        fn to_shares(&self, amount: Balance) -> Balance {
+            Self::mul_div(amount, self.rebase_den, self.rebase_num)
+        }
+
+        /// Converts shares to a token amount, rounding down.
+        fn to_amount(&self, shares: Balance) -> Balance {
+            Self::mul_div(shares, self.rebase_num, self.rebase_den)
+        }
+
+        /// Converts a nonzero token amount to shares, rounding down, or reverts with
+        /// `Custom("amount below one share")` if it is worth none.
+        fn shares_for(&self, value: Balance) -> PSP22Result<Balance> {
+            let shares = self.to_shares(value);
+
+            if value > 0 && shares == 0 {
+                return Err(PSP22Error::Custom(String::from("amount below one share")));
+            }
+
+            Ok(shares)
+        }
+
+        /// Returns the shares leaving an account holding `held` shares when `value`
+        /// tokens are taken from it: all of them for its whole balance, so it can
+        /// always be emptied, otherwise `shares_for(value)`.
+        ///
+        /// The amount actually moved is `to_amount` of the result; callers report
+        /// that amount rather than `value`, which it can fall short of after a rebase.
+        fn shares_out(&self, held: Balance, value: Balance) -> PSP22Result<Balance> {
+            if value == self.to_amount(held) {
+                return Ok(held);
+            }
+
+            self.shares_for(value)
+        }
+
+        /// Writes the shares of `account`, keeping `holder_count` and `ever_held` in sync.
+        ///
+        /// `previous` must be the shares currently stored for `account`.
+        fn write_shares(&mut self, account: AccountId, previous: Balance, shares: Balance) {
+            if previous == 0 && shares > 0 {
+                self.holder_count += 1;
+
+                if !self.ever_held.contains(account) {
+                    self.ever_held.insert(account, &true);
+                }
+            } else if previous > 0 && shares == 0 {
+                self.holder_count -= 1;
+            }
+
+            // Saturating so corrupted state surfaces through `check_supply_invariant`
+            // instead of a panic.
+            self.balance_sum = self
+                .balance_sum
+                .saturating_sub(previous)
+                .saturating_add(shares);
+
+            if shares == 0 {
+                self.balances.remove(account);
+            } else {
+                self.balances.insert(account, &shares);
+            }
+        }
+
+        /// Moves `value` tokens between accounts without the recipient checks of
+        /// `transfer`, for internal escrow movements. A `freeze_all`, frozen accounts
+        /// and, while KYC is required, an unverified `to` are still rejected. Emits a
+        /// `Transfer` event.
+        fn move_balance(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> PSP22Result<()> {
+            if value == 0 {
+                return Ok(());
+            }
+
+            let from_shares = self.shares_of(from);
+
+            if self.to_amount(from_shares) < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            let shares = self.shares_out(from_shares, value)?;
+            self.move_shares(from, to, shares)
+        }
+
+        /// Moves `shares` between accounts with the checks of `move_balance`, for
+        /// escrow tracked in shares. Emits a `Transfer` event for their value.
+        fn move_shares(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            shares: Balance,
+        ) -> PSP22Result<()> {
+            if shares == 0 {
+                return Ok(());
+            }
+
+            self.ensure_not_frozen_all()?;
+
+            if self.is_frozen(from) || self.is_frozen(to) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            self.ensure_kyc(&[to])?;
+
+            let from_shares = self.shares_of(from);
+
+            if from_shares < shares {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            let recipient_shares = self.shares_of(to);
+            self.write_shares(from, from_shares, from_shares - shares);
+            self.write_shares(to, recipient_shares, recipient_shares + shares);
+
+            self.emit_transfer(Some(from), Some(to), self.to_amount(shares), vec![]);
+
+            Ok(())
+        }
+
+        /// Returns how much of `stream` has accrued to the recipient, capped at the deposit.
+        fn stream_accrued(&self, stream: &Stream) -> Balance {
+            // Block timestamps are in milliseconds.
+            let elapsed_secs = self.env().block_timestamp().saturating_sub(stream.start) / 1_000;
+
+            stream
+                .rate_per_sec
+                .saturating_mul(Balance::from(elapsed_secs))
+                .min(stream.deposit)
+        }
+
+        /// Returns how many shares of `schedule` have vested: nothing before the
+        /// cliff, then linearly up to `total` at the end of the duration.
+        fn vested(&self, schedule: &VestingSchedule) -> Balance {
+            let elapsed = self.env().block_timestamp().saturating_sub(schedule.start);
+
+            if elapsed < schedule.cliff {
+                return 0;
+            }
+
+            if elapsed >= schedule.duration {
+                return schedule.total;
+            }
+
+            Self::mul_div(
+                schedule.total,
+                Balance::from(elapsed),
+                Balance::from(schedule.duration),
+            )
+        }
+
+        /// Creates `value` new tokens on the account `to`, rounded down to whole shares.
+        ///
+        /// A `Transfer` event with `from: None` is emitted for the amount created.
+        fn mint_to(&mut self, to: AccountId, value: Balance) -> PSP22Result<()> {
+            if to == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            if self.is_frozen(to) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            self.ensure_kyc(&[to])?;
+
+            let shares = self.shares_for(value)?;
+            let value = self.to_amount(shares);
+            let total_supply = self
+                .total_supply
+                .checked_add(shares)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+            let total_minted = self
+                .total_minted
+                .checked_add(value)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+            let previous = self.shares_of(to);
+            self.total_supply = total_supply;
+            self.total_minted = total_minted;
+            self.write_shares(to, previous, previous + shares);
+
+            self.emit_transfer(None, Some(to), value, vec![]);
+
+            Ok(())
+        }
+
+        /// Destroys `value` tokens out of the `held` shares of `account` and returns
+        /// the amount destroyed, see `shares_out`. Reverts during a `freeze_all`.
+        /// Emitting the `Transfer` to `None` is left to the caller.
+        fn burn_from(
+            &mut self,
+            account: AccountId,
+            held: Balance,
+            value: Balance,
+        ) -> PSP22Result<Balance> {
+            self.ensure_not_frozen_all()?;
+
+            let shares = self.shares_out(held, value)?;
+            let value = self.to_amount(shares);
+            let total_supply = self
+                .total_supply
+                .checked_sub(shares)
+                .ok_or_else(|| PSP22Error::Custom(String::from("supply underflow")))?;
+            let total_burned = self
+                .total_burned
+                .checked_add(value)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+
+            self.write_shares(account, held, held - shares);
+            self.total_supply = total_supply;
+            self.total_burned = total_burned;
+
+            Ok(value)
+        }
+
+        /// Returns the airdrop merkle leaf for `amount` to `account`.
+        fn merkle_leaf(account: AccountId, amount: Balance) -> [u8; 32] {
+            let mut input = Vec::with_capacity(32 + 16);
+            input.extend_from_slice(account.as_ref());
+            input.extend_from_slice(&amount.to_le_bytes());
+
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<ink::env::hash::Blake2x256>(&input, &mut output);
+            output
+        }
+
+        /// Returns the merkle node above `a` and `b`, hashed in sorted order so
+        /// proofs don't need to encode left/right positions.
+        fn merkle_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+            let (first, second) = if a <= b { (a, b) } else { (b, a) };
+            let mut input = [0u8; 64];
+            input[..32].copy_from_slice(first);
+            input[32..].copy_from_slice(second);
+
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<ink::env::hash::Blake2x256>(&input, &mut output);
+            output
+        }
+
+        /// Sets the allowance an owner grants `spender`, logs it in `approval_log` and
+        /// emits `AllowanceChanged`.
+        ///
+        /// `previous` must be the allowance currently stored. Allowance spent through
+        /// `transfer_from` is not logged; the log shows what the owner last granted.
+        fn set_approval(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            previous: Balance,
+            value: Balance,
+        ) -> PSP22Result<()> {
+            self.track_approval_count(owner, previous, value)?;
+            self.allowances.insert((owner, spender), &value);
+            self.approval_log
+                .insert((owner, spender), &(value, self.env().block_number()));
+            self.env().emit_event(AllowanceChanged {
+                owner,
+                spender,
+                old: previous,
+                new: value,
+            });
+
+            Ok(())
+        }
+
+        /// Updates `approval_count` for an allowance of `owner` changing from
+        /// `previous` to `value`, rejecting a new nonzero one past
+        /// `max_approvals_per_owner` with `Custom("too many approvals")`.
+        fn track_approval_count(
+            &mut self,
+            owner: AccountId,
+            previous: Balance,
+            value: Balance,
+        ) -> PSP22Result<()> {
+            if previous == 0 && value != 0 {
+                let count = self.approval_count(owner);
+
+                if count >= self.max_approvals_per_owner {
+                    return Err(PSP22Error::Custom(String::from("too many approvals")));
+                }
+
+                self.approval_count.insert(owner, &(count + 1));
+            } else if previous != 0 && value == 0 {
+                match self.approval_count(owner).saturating_sub(1) {
+                    0 => self.approval_count.remove(owner),
+                    count => {
+                        self.approval_count.insert(owner, &count);
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Returns the fee charged on a transfer of `value` from `from` to `to`.
+        fn transfer_fee(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> PSP22Result<Balance> {
+            if self.transfer_fee_bps == 0 || self.is_fee_exempt(from) || self.is_fee_exempt(to) {
+                return Ok(0);
+            }
+
+            let fee = value
+                .checked_mul(u128::from(self.transfer_fee_bps))
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?
+                / BPS_DENOMINATOR;
+
+            Ok(fee)
+        }
+
+        /// Runs every check `transfer_from` makes by `caller` except pause and data
+        /// size, without touching storage.
+        fn validate_transfer_from(
+            &self,
+            caller: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> PSP22Result<TransferPlan> {
+            let is_operator = self.is_operator(from, caller);
+            let allowance = self.allowance(from, caller);
+            let (debit, fee) = self.transfer_debit(from, to, value)?;
+
+            // The allowance covers everything leaving `from`, fee included.
+            if !is_operator && allowance < debit {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+
+            Ok(TransferPlan {
+                remaining_allowance: (!is_operator).then(|| allowance - debit),
+                ..self.validate_transfer(from, to, value, debit, fee)?
+            })
+        }
+
+        /// Runs every check a transfer of `value` from `from` to `to` must pass,
+        /// whoever initiates it, except pause and data size, without touching storage.
+        ///
+        /// `debit` and `fee` are what `transfer_debit` returns for the transfer.
+        fn validate_transfer(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            debit: Balance,
+            fee: Balance,
+        ) -> Result<TransferPlan, PSP22Error> {
+            let from_shares = self.shares_of(from);
+
+            if self.to_amount(from_shares) < debit {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+
+            if from == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroSenderAddress);
+            }
+
+            if to == AccountId::from([0u8; 32]) && !self.zero_address_burns {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            if self.is_frozen(from) || self.is_frozen(to) {
+                return Err(PSP22Error::Custom(String::from("account frozen")));
+            }
+
+            self.ensure_not_frozen_all()?;
+            self.ensure_transfer_allowed(from)?;
+            self.ensure_kyc(&[from, to])?;
+            self.ensure_whole_units(value)?;
+
+            if let Some(limit) = self.daily_limit {
+                if value > 0 && from != to && to != AccountId::from([0u8; 32]) {
+                    self.check_daily_volume(from, debit, limit)?;
+                }
+            }
+
+            if to == self.env().account_id() {
+                return Err(PSP22Error::Custom(String::from(
+                    "transfer to token contract",
+                )));
+            }
+
+            if self.env().is_contract(&to) && !self.is_recipient_contract_allowed(to) {
+                return Err(PSP22Error::SafeTransferCheckFailed(format!(
+                    "AccountId {:?} is contract",
+                    &to
+                )));
+            }
+
+            Ok(TransferPlan {
+                debit,
+                fee,
+                from_shares,
+                remaining_allowance: None,
+            })
+        }
+
+        /// Adds `value` to what `from` has sent today, rejecting it with
+        /// `Custom("daily limit exceeded")` if that would pass `daily_limit`.
+        fn record_daily_volume(&mut self, from: AccountId, value: Balance) -> PSP22Result<()> {
+            let Some(limit) = self.daily_limit else {
+                return Ok(());
+            };
+
+            let today = self.env().block_timestamp() / MS_PER_DAY;
+            let volume = self.check_daily_volume(from, value, limit)?;
+            self.daily_volume.insert(from, &(today, volume));
+
+            Ok(())
+        }
+
+        /// Returns what `from` will have sent today after another `value`, or
+        /// `Custom("daily limit exceeded")` if that passes `limit`.
+        fn check_daily_volume(
+            &self,
+            from: AccountId,
+            value: Balance,
+            limit: Balance,
+        ) -> PSP22Result<Balance> {
+            self.daily_volume(from)
+                .checked_add(value)
+                .filter(|volume| *volume <= limit)
+                .ok_or_else(|| PSP22Error::Custom(String::from("daily limit exceeded")))
+        }
+
+        /// Returns `(debit, fee)` for a transfer of `value` from `from` to `to`:
+        /// what leaves `from` and, out of it, what goes to `fee_recipient`.
+        ///
+        /// Zero-value and self transfers move nothing and, like burns to the zero
+        /// address, are never charged.
+        fn transfer_debit(
+            &self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> PSP22Result<(Balance, Balance)> {
+            if value == 0 || from == to || to == AccountId::from([0u8; 32]) {
+                return Ok((value, 0));
+            }
+
+            let fee = self.transfer_fee(from, to, value)?;
+
+            match self.fee_mode {
+                FeeMode::Inclusive => Ok((value, fee)),
+                FeeMode::Exclusive => Ok((
+                    value
+                        .checked_add(fee)
+                        .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?,
+                    fee,
+                )),
+            }
+        }
+
+        /// Debits `debit` from the `from_shares` of `from`, credits `to` with
+        /// `debit - fee` and `fee_recipient` with `fee`.
+        ///
+        /// Returns what `to` and `fee_recipient` actually received, which a rebase
+        /// can round below `debit - fee` and `fee`; see `shares_out`.
+        fn settle_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            from_shares: Balance,
+            debit: Balance,
+            fee: Balance,
+        ) -> PSP22Result<(Balance, Balance)> {
+            self.record_daily_volume(from, debit)?;
+            let overflow = || PSP22Error::Custom(String::from("overflow"));
+            let debit_shares = self.shares_out(from_shares, debit)?;
+            let fee_shares = self.to_shares(fee).min(debit_shares);
+            self.record_transfer(from, to, self.to_amount(debit_shares))?;
+
+            // Neither party is the fee recipient, who is always exempt.
+            let fee_recipient = self.fee_recipient;
+            let recipient_before = self.shares_of(to);
+            let fee_before = if fee_shares > 0 {
+                self.shares_of(fee_recipient)
+            } else {
+                0
+            };
+            let from_after = from_shares - debit_shares;
+            let recipient_after = recipient_before
+                .checked_add(debit_shares - fee_shares)
+                .ok_or_else(overflow)?;
+            let fee_after = fee_before.checked_add(fee_shares).ok_or_else(overflow)?;
+
+            // What `to` and `fee_recipient` end up with must add up to exactly what
+            // leaves `from`, so no rounding ever creates or destroys tokens.
+            let received_shares = recipient_after - recipient_before;
+            let credited = received_shares.checked_add(fee_after - fee_before);
+            if credited != Some(from_shares - from_after) {
+                return Err(PSP22Error::Custom(String::from("fee split mismatch")));
+            }
+
+            self.write_shares(from, from_shares, from_after);
+            self.write_shares(to, recipient_before, recipient_after);
+
+            if fee_shares > 0 {
+                self.write_shares(fee_recipient, fee_before, fee_after);
+            }
+
+            Ok((self.to_amount(received_shares), self.to_amount(fee_shares)))
+        }
+
+        /// Emits a `Transfer` event with the next sequence number.
+        fn emit_transfer(
+            &mut self,
+            from: Option<AccountId>,
+            to: Option<AccountId>,
+            value: Balance,
+            data: Vec<u8>,
+        ) {
+            self.transfer_seq += 1;
+            self.env().emit_event(Transfer {
+                from,
+                to,
+                value,
+                data,
+                seq: self.transfer_seq,
+            });
+        }
+
+        /// Emits the `Transfer` event for a fee taken from `from`, if any.
+        fn emit_fee_transfer(&mut self, from: AccountId, fee: Balance) {
+            if fee > 0 {
+                self.emit_transfer(Some(from), Some(self.fee_recipient), fee, Vec::new());
+            }
+        }
+
+        /// Marks a flash loan active and mints `amount` to `receiver`, returning the
+        /// fee it owes. Reverts with `Custom("flash loan active")` if one already is.
+        fn open_flash_loan(
+            &mut self,
+            receiver: AccountId,
+            amount: Balance,
+        ) -> PSP22Result<Balance> {
+            if self.flash_loan_active {
+                return Err(PSP22Error::Custom(String::from("flash loan active")));
+            }
+
+            let fee = self.flash_fee(amount)?;
+            self.flash_loan_active = true;
+            self.mint_to(receiver, amount)?;
+
+            Ok(fee)
+        }
+
+        /// Burns the flash-loaned `amount` from `receiver`, moves `fee` to
+        /// `fee_recipient` and ends the loan.
+        ///
+        /// The principal only exists for the duration of the loan, so its mint and
+        /// burn are taken back out of `total_minted` and `total_burned`.
+        fn settle_flash_loan(
+            &mut self,
+            receiver: AccountId,
+            amount: Balance,
+            fee: Balance,
+        ) -> PSP22Result<()> {
+            let shares = self.shares_of(receiver);
+            let owed = amount
+                .checked_add(fee)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+
+            if self.to_amount(shares) < owed {
+                return Err(PSP22Error::Custom(String::from("flash loan not repaid")));
+            }
+
+            let burned = self.burn_from(receiver, shares, amount)?;
+            self.emit_transfer(Some(receiver), None, burned, Vec::new());
+            if receiver != self.fee_recipient {
+                self.move_balance(receiver, self.fee_recipient, fee)?;
+            }
+
+            let minted = self.to_amount(self.to_shares(amount));
+            self.total_minted = self.total_minted.saturating_sub(minted);
+            self.total_burned = self.total_burned.saturating_sub(burned);
+            self.flash_loan_active = false;
+
+            Ok(())
+        }
+
+        /// Adds `value` to the lifetime total sent from `from` to `to`.
+        fn record_transfer(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> PSP22Result<()> {
+            let total = self
+                .total_transferred(from, to)
+                .checked_add(value)
+                .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?;
+            self.transferred_between.insert((from, to), &total);
+
+            Ok(())
+        }
+
+        /// Returns `Custom("self approval")` if `owner` approves itself while that is disallowed.
+        fn ensure_not_self_approval(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> PSP22Result<()> {
+            if owner == spender && !self.allow_self_approval {
+                return Err(PSP22Error::Custom(String::from("self approval")));
+            }
+
+            Ok(())
+        }
+
+        /// Applies the `max_allowance` cap, the `min_approval` floor and then the
+        /// optional clamp to `owner`'s balance to a prospective allowance.
+        fn capped_allowance(&self, owner: AccountId, value: Balance) -> PSP22Result<Balance> {
+            let value = if value <= self.max_allowance {
+                value
+            } else if self.clamp_allowance {
+                self.max_allowance
+            } else {
+                return Err(PSP22Error::Custom(String::from("allowance too high")));
+            };
+
+            // Checked before the balance clamp: a small balance is not the owner
+            // asking for a small allowance.
+            if value != 0 && value < self.min_approval {
+                return Err(PSP22Error::Custom(String::from("approval too small")));
+            }
+
+            if self.cap_allowance_to_balance {
+                return Ok(value.min(self.balance_of(owner)));
+            }
+
+            Ok(value)
+        }
+    }
+
+    impl PSP22 for RedToken {
+        /// Returns the total token supply
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.to_amount(self.total_supply)
+        }
+
+        /// Returns the account balance for the specified `owner`
+        /// Returns `0` if the account is non-existent
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.to_amount(self.shares_of(owner))
+        }
+
+        //  "Returns the amount which `spender` is still allowed to withdraw from `owner`.",
+        //  "Returns `0` if no allowance has been set."
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or(0)
+        }
+
+        ///  "Transfers `value` amount of tokens from the caller's account to account `to`",
+        ///  "with additional `data` in unspecified format.",
+        ///  "",
+        ///  "On success a `Transfer` event is emitted.",
+        ///  "A zero `value` succeeds without touching balances and still emits the event.",
+        ///  "",
+        ///  "# Errors",
+        ///  "",
+        ///  "Reverts with error `InsufficientBalance` if there are not enough tokens on",
+        ///  "the caller's account Balance.",
+        ///  "",
+        ///  "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
+        ///  "",
+        ///  "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        ///  "Reverts with error `Custom(\"data too large\")` if `data` exceeds `MAX_DATA_BYTES`."
+        ///  "Reverts with error `SafeTransferCheckFailed` if the recipient is a contract not"
+        ///  "on the recipient contract allowlist, or one that rejected the transfer in"
+        ///  "`PSP22Receiver::before_received`."
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            Self::ensure_data_size(&data)?;
+
+            let sender = self.env().caller();
+            let (debit, fee) = self.transfer_debit(sender, to, value)?;
+            let TransferPlan { from_shares, .. } =
+                self.validate_transfer(sender, to, value, debit, fee)?;
+            let burn = to == AccountId::from([0u8; 32]);
+
+            // Zero-value and self transfers are valid no-ops: the event is emitted,
+            // balances are untouched.
+            let (received, fee) = if value > 0 && burn {
+                (self.burn_from(sender, from_shares, value)?, 0)
+            } else if value > 0 && sender != to {
+                self.settle_transfer(sender, to, from_shares, debit, fee)?
+            } else {
+                (debit - fee, fee)
+            };
+
+            self.ensure_receiver_accepts(sender, sender, to, received, &data)?;
+
+            self.emit_transfer(Some(sender), (!burn).then_some(to), received, data);
+            self.emit_fee_transfer(sender, fee);
+
+            Ok(())
+        }
+
+        /// "Transfers `value` tokens on the behalf of `from` to the account `to`",
+        /// "with additional `data` in unspecified format.",
+        /// "",
+        /// "This can be used to allow a contract to transfer tokens on ones behalf and/or",
+        /// "to charge fees in sub-currencies, for example.",
+        /// "",
+        /// "On success a `Transfer` and `Approval` events are emitted. The allowance of the",
+        /// "caller is reduced by `value`, plus the fee in `FeeMode::Exclusive`, and the",
+        /// "`Approval` event carries what remains.",
+        /// "A zero `value` succeeds without touching balances and still emits the events.",
+        /// "",
+        /// "Operator approval takes precedence: an operator of `from` may move any amount,",
+        /// "even with a zero or insufficient allowance. The numeric allowance is neither",
+        /// "checked nor reduced, and no `Approval` event is emitted.",
+        /// "",
+        /// "# Errors",
+        /// "",
+        /// "Reverts with error `InsufficientAllowance` if there are not enough tokens allowed",
+        /// "for the caller to withdraw from `from`.",
+        /// "",
+        /// "Reverts with error `InsufficientBalance` if there are not enough tokens on",
+        /// "the the account Balance of `from`.",
+        /// "",
+        /// "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
+        /// "",
+        /// "Reverts with error `ZeroRecipientAddress` if recipient's address is zero,"
+        /// "unless `zero_address_burns` is set, in which case the tokens are burned."
+        /// "Reverts with error `Custom(\"data too large\")` if `data` exceeds `MAX_DATA_BYTES`."
+        /// "Reverts with error `SafeTransferCheckFailed` if the recipient is a contract"
+        /// "not on the recipient contract allowlist, or one that rejected the transfer in"
+        /// "`PSP22Receiver::before_received`, called with the caller as `operator`."
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+            Self::ensure_data_size(&data)?;
+
+            let caller = self.env().caller();
+            let TransferPlan {
+                debit,
+                fee,
+                from_shares,
+                remaining_allowance,
+            } = self.validate_transfer_from(caller, from, to, value)?;
+
+            // Zero-value transfers are valid no-ops: events are emitted, storage is untouched.
+            // Self transfers consume allowance but leave balances untouched.
+            let burn = to == AccountId::from([0u8; 32]);
+            let (received, fee) = if value > 0 && burn {
+                (self.burn_from(from, from_shares, value)?, 0)
+            } else if value > 0 && from != to {
+                self.settle_transfer(from, to, from_shares, debit, fee)?
+            } else {
+                (debit - fee, fee)
+            };
+
+            // The allowance is charged the requested `debit`, never less than moved.
+            if let Some(remaining) = remaining_allowance.filter(|_| value > 0) {
+                self.allowances.insert((from, caller), &remaining);
+                self.track_approval_count(from, remaining + debit, remaining)?;
+            }
+
+            self.ensure_receiver_accepts(caller, from, to, received, &data)?;
+
+            self.emit_transfer(Some(from), (!burn).then_some(to), received, data.clone());
+            self.emit_fee_transfer(from, fee);
+
+            if let Some(remaining) = remaining_allowance {
+                self.env().emit_event(AllowanceChanged {
+                    owner: from,
+                    spender: caller,
+                    old: remaining + debit,
+                    new: remaining,
+                });
+                self.env().emit_event(Approval {
+                    owner: from,
+                    spender: caller,
+                    value: remaining,
+                    data,
+                });
+            }
+
+            Ok(())
+        }
+
+        ///    "Allows `spender` to withdraw from the caller's account multiple times, up to",
+        ///    "the `value` amount.",
+        ///    "",
+        ///    "If this function is called again it overwrites the current allowance with `value`.",
+        ///    "",
+        ///    "An `Approval` event is emitted, followed by `Revocation` if a nonzero",
+        ///    "allowance is set to zero.",
+        ///    "",
+        ///    "# Errors",
+        ///    "",
+        ///    "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
+        ///    "",
+        ///    "Reverts with error `ZeroRecipientAddress` if recipient's address is zero.",
+        ///    "",
+        ///    "Reverts with error `Custom(\"allowance too high\")` if `value` exceeds",
+        ///    "`max_allowance` and clamping is disabled.",
+        ///    "",
+        ///    "Reverts with error `Custom(\"allowance not zero\")` if a zero step is required",
+        ///    "before changing a nonzero allowance."
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error> {
+            self.approve_with_data(spender, value, vec![])
+        }
+
+        ///    "Atomically increases the allowance granted to `spender` by the caller.",
+        //     "",
+        //     "An `Approval` event is emitted.",
+        //     "",
+        //     "# Errors",
+        //     "",
+        //     "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
+        //     "",
+        //     "Reverts with error `ZeroRecipientAddress` if recipient's address is zero.",
+        //     "",
+        //     "Reverts with error `Custom(\"allowance too high\")` if the resulting allowance",
+        //     "exceeds `max_allowance` and clamping is disabled."
+        #[ink(message)]
+        fn increase_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let caller = self.env().caller();
+
+            if caller == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroSenderAddress);
+            }
+
+            if spender == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            self.ensure_not_self_approval(caller, spender)?;
+
+            // Read only once the addresses are validated; a missing entry is a zero
+            // allowance, so no prior `approve` is needed.
+            let current_allowence = self.allowance(caller, spender);
+            // Caps and clamps may hold an increase back, never turn it into a decrease.
+            let new_allowance = self
+                .capped_allowance(
+                    caller,
+                    current_allowence
+                        .checked_add(delta_value)
+                        .ok_or_else(|| PSP22Error::Custom(String::from("overflow")))?,
+                )?
+                .max(current_allowence);
+            self.set_approval(caller, spender, current_allowence, new_allowance)?;
+
+            self.env().emit_event(Approval {
+                owner: caller,
+                spender,
+                value: new_allowance,
+                data: vec![],
+            });
+
+            Ok(())
+        }
+
+        ///    "Atomically decreases the allowance granted to `spender` by the caller.",
+        //     "",
+        //     "An `Approval` event is emitted.",
+        //     "",
+        //     "# Errors",
+        //     "",
+        //     "Reverts with error `InsufficientAllowance` if there are not enough tokens allowed",
+        //     "by owner for `spender`, unless `saturating_decrease` floors it at zero.",
+        //     "",
+        //     "Reverts with error `ZeroSenderAddress` if sender's address is zero.",
+        //     "",
+        //     "Reverts with error `ZeroRecipientAddress` if recipient's address is zero."
+        #[ink(message)]
+        fn decrease_allowance(
+            &mut self,
+            spender: AccountId,
+            delta_value: Balance,
+        ) -> Result<(), PSP22Error> {
+            self.ensure_not_paused()?;
+
+            let caller = self.env().caller();
+
+            if caller == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroSenderAddress);
+            }
+
+            if spender == AccountId::from([0u8; 32]) {
+                return Err(PSP22Error::ZeroRecipientAddress);
+            }
+
+            let current_allowence = self.allowance(caller, spender);
+
+            if current_allowence < delta_value && !self.saturating_decrease {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+
+            let new_allowance = current_allowence.saturating_sub(delta_value);
+            self.set_approval(caller, spender, current_allowence, new_allowance)?;
+
+            self.env().emit_event(Approval {
+                owner: caller,
+                spender,
+                value: new_allowance,
+                data: vec![],
+            });
+
+            Ok(())
+        }
+    }
+
+    impl PSP22Metadata for RedToken {
+        /// Returns the token name, or `None` if metadata is hidden.
+        #[ink(message)]
+        fn token_name(&self) -> Option<String> {
+            (!self.hide_metadata).then(|| self.token_name.clone())
+        }
+
+        /// Returns the token symbol, or `None` if metadata is hidden.
+        #[ink(message)]
+        fn token_symbol(&self) -> Option<String> {
+            (!self.hide_metadata).then(|| self.token_symbol.clone())
+        }
+
+        /// Returns the token decimals.
+        #[ink(message)]
+        fn token_decimals(&self) -> u8 {
+            self.token_decimals
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use ink::primitives::AccountId;
+
+        use super::*;
+
+        type Event = <RedToken as ink::reflect::ContractEventBase>::Type;
+
+        fn decode_event(event: &ink::env::test::EmittedEvent) -> Event {
+            <Event as scale::Decode>::decode(&mut &event.data[..]).expect("invalid event data")
+        }
+
+        /// Recorded events other than the `AdminAction` audit trail.
+        fn token_events() -> impl Iterator<Item = ink::env::test::EmittedEvent> {
+            ink::env::test::recorded_events()
+                .filter(|event| !matches!(decode_event(event), Event::AdminAction(_)))
+        }
+
+        /// Address the contract under test runs at.
+        const CONTRACT: [u8; 32] = [0xC0; 32];
+
+        fn default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<ink::env::DefaultEnvironment>()
+        }
+
+        /// Moves the contract under test to `CONTRACT` and returns the default test
+        /// accounts.
+        ///
+        /// The off-chain engine runs the contract at alice's address by default, which
+        /// would make alice the token contract itself. Call it before constructing the
+        /// contract.
+        fn accounts_with_contract_callee(
+        ) -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::set_callee::<ink::env::DefaultEnvironment>(AccountId::from(CONTRACT));
+            default_accounts()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller);
+        }
+
+        #[ink::test]
+        fn test_init() {
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 5u8);
+            assert_eq!(
+                contract.token_name().unwrap(),
+                "Real Estate DAO".to_string()
+            );
+        }
+
+        #[ink::test]
+        fn version_getters_return_compiled_constants() {
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 5u8);
+            assert_eq!(contract.version(), VERSION);
+            assert_eq!(contract.version(), (0, 1, 0));
+            assert_eq!(contract.storage_schema_id(), STORAGE_SCHEMA_ID);
+        }
+
+        #[ink::test]
+        fn set_price_updates_price_and_emits_timestamped_event() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_700_000);
+
+            assert_eq!(contract.set_price_decimals(6), Ok(()));
+            assert_eq!(contract.set_price(1_250_000), Ok(()));
+            assert_eq!(contract.last_price(), 1_250_000);
+            assert_eq!(contract.price_decimals(), 6);
+
+            let events: Vec<_> = token_events().collect();
+            assert_eq!(events.len(), 1);
+            match decode_event(&events[0]) {
+                Event::PriceUpdated(PriceUpdated { price, timestamp }) => {
+                    assert_eq!(price, 1_250_000);
+                    assert_eq!(timestamp, 1_700_000);
+                }
+                _ => panic!("expected PriceUpdated event"),
+            }
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_price(1),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            assert_eq!(contract.last_price(), 1_250_000);
+        }
+
+        #[ink::test]
+        fn transfer_from_rejects_contract_recipient() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 500), Ok(()));
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.eve);
+
+            set_caller(accounts.bob);
+            assert!(matches!(
+                contract.transfer_from(accounts.alice, accounts.eve, 100, vec![]),
+                Err(PSP22Error::SafeTransferCheckFailed(_))
+            ));
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 100, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+        }
+
+        #[ink::test]
+        fn format_amount_splits_whole_and_fraction() {
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 5u8);
+            assert_eq!(contract.format_amount(150_000), (1, 50_000));
+            assert_eq!(contract.format_amount(300_000), (3, 0));
+            assert_eq!(contract.format_amount(42), (0, 42));
+
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 2u8);
+            assert_eq!(contract.format_amount(1_234), (12, 34));
+            assert_eq!(contract.format_amount(1_200), (12, 0));
+
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 0u8);
+            assert_eq!(contract.format_amount(1_234), (1_234, 0));
+        }
+
+        #[ink::test]
+        fn max_allowance_rejects_or_clamps_oversized_grants() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_max_allowance(1_000, false), Ok(()));
+
+            assert_eq!(contract.approve(accounts.bob, 1_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+            assert_eq!(
+                contract.approve(accounts.charlie, 1_001),
+                Err(PSP22Error::Custom(String::from("allowance too high")))
+            );
+            assert_eq!(
+                contract.increase_allowance(accounts.bob, 1),
+                Err(PSP22Error::Custom(String::from("allowance too high")))
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+
+            assert_eq!(contract.set_max_allowance(1_000, true), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 5_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.charlie), 1_000);
+            assert_eq!(contract.approve(accounts.django, 900), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.django, 500), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.django), 1_000);
+        }
+
+        #[ink::test]
+        fn health_matches_individual_getters() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.health(), (false, 1_000, 1));
+
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 100, vec![]), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 99, vec![]), Ok(()));
+            assert_eq!(contract.holder_count(), 3);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.pause(), Ok(()));
+            assert_eq!(
+                contract.health(),
+                (
+                    contract.paused(),
+                    contract.total_supply(),
+                    contract.holder_count()
+                )
+            );
+            assert_eq!(contract.health(), (true, 1_000, 3));
+        }
+
+        #[ink::test]
+        fn transfer_tagged_moves_balance_and_emits_tag() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.transfer_tagged(accounts.bob, 250, *b"rent", vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 750);
+            assert_eq!(contract.balance_of(accounts.bob), 250);
+
+            let events: Vec<_> = ink::env::test::recorded_events().collect();
+            assert_eq!(events.len(), 2);
+            assert!(matches!(decode_event(&events[0]), Event::Transfer(_)));
+            match decode_event(&events[1]) {
+                Event::TaggedTransfer(TaggedTransfer {
+                    from,
+                    to,
+                    value,
+                    tag,
+                }) => {
+                    assert_eq!(from, accounts.alice);
+                    assert_eq!(to, accounts.bob);
+                    assert_eq!(value, 250);
+                    assert_eq!(tag, *b"rent");
+                }
+                _ => panic!("expected TaggedTransfer event"),
+            }
+            assert_eq!(events[1].topics.len(), 2);
+        }
+
+        #[ink::test]
+        fn get_amount_out_matches_reference_values() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            // Default 0.3% fee, as in Uniswap v2.
+            assert_eq!(contract.get_amount_out(1_000, 10_000, 10_000), Ok(906));
+            assert_eq!(
+                contract.get_amount_out(1_000_000, 100_000_000, 50_000_000),
+                Ok(493_579)
+            );
+
+            assert_eq!(contract.set_swap_fee_bps(0), Ok(()));
+            assert_eq!(contract.get_amount_out(1_000, 10_000, 10_000), Ok(909));
+
+            assert_eq!(
+                contract.set_swap_fee_bps(10_001),
+                Err(PSP22Error::Custom(String::from("fee too high")))
+            );
+        }
+
+        #[ink::test]
+        fn get_amount_out_rejects_zero_reserves() {
+            let contract = RedToken::new(100_000, AccountId::from([0x01; 32]), 5u8);
+            let insufficient_liquidity =
+                Err(PSP22Error::Custom(String::from("insufficient liquidity")));
+
+            assert_eq!(
+                contract.get_amount_out(1_000, 0, 10_000),
+                insufficient_liquidity
+            );
+            assert_eq!(
+                contract.get_amount_out(1_000, 10_000, 0),
+                insufficient_liquidity
+            );
+        }
+
+        #[ink::test]
+        fn zero_value_transfers_emit_event_without_touching_balances() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 0), Ok(()));
+
+            assert_eq!(contract.transfer(accounts.charlie, 0, vec![]), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 0, vec![]),
+                Ok(())
+            );
+            // An empty account can send zero as well.
+            assert_eq!(contract.transfer(accounts.charlie, 0, vec![]), Ok(()));
+
+            assert_eq!(contract.balance_of(accounts.alice), 1_000);
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+            assert!(!contract.balances.contains(accounts.charlie));
+            assert_eq!(contract.total_supply(), 1_000);
+            assert_eq!(contract.holder_count(), 1);
+
+            let transfers = ink::env::test::recorded_events()
+                .filter(|event| matches!(decode_event(event), Event::Transfer(_)))
+                .count();
+            assert_eq!(transfers, 3);
+        }
+
+        #[ink::test]
+        fn transfer_of_entire_balance_succeeds() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 0);
+            assert_eq!(contract.balance_of(accounts.bob), 1_000);
+        }
+
+        #[ink::test]
+        fn reads_stay_available_while_paused() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+            assert_eq!(contract.pause(), Ok(()));
+
+            assert_eq!(contract.total_supply(), 1_000);
+            assert_eq!(contract.balance_of(accounts.alice), 1_000);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 300);
+            assert_eq!(contract.token_name(), Some(String::from("Real Estate DAO")));
+            assert_eq!(contract.token_symbol(), Some(String::from("RED")));
+            assert_eq!(contract.token_decimals(), 5);
+            assert_eq!(contract.health(), (true, 1_000, 1));
+        }
+
+        #[ink::test]
+        fn writes_fail_while_paused() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+            assert_eq!(contract.pause(), Ok(()));
+            let paused = Err(PSP22Error::Custom(String::from("paused")));
+
+            assert_eq!(contract.transfer(accounts.bob, 10, vec![]), paused);
+            assert_eq!(
+                contract.transfer_tagged(accounts.bob, 10, *b"tag0", vec![]),
+                paused
+            );
+            assert_eq!(contract.approve(accounts.bob, 10), paused);
+            assert_eq!(contract.increase_allowance(accounts.bob, 10), paused);
+            assert_eq!(contract.decrease_allowance(accounts.bob, 10), paused);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 10, vec![]),
+                paused
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.unpause(), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10, vec![]), Ok(()));
+        }
+
+        #[test]
+        fn metadata_selectors_match_psp22_standard() {
+            assert_eq!(
+                ink::selector_bytes!("PSP22Metadata::token_name"),
+                [0x3d, 0x26, 0x1b, 0xd4]
+            );
+            assert_eq!(
+                ink::selector_bytes!("PSP22Metadata::token_symbol"),
+                [0x34, 0x20, 0x5b, 0xe5]
+            );
+            assert_eq!(
+                ink::selector_bytes!("PSP22Metadata::token_decimals"),
+                [0x72, 0x71, 0xb7, 0x82]
+            );
+        }
+
+        #[ink::test]
+        fn set_token_decimals_only_before_issuance() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+
+            let mut contract = RedToken::new(0, accounts.alice, 5u8);
+            assert_eq!(contract.set_token_decimals(18), Ok(()));
+            assert_eq!(contract.token_decimals(), 18);
+
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(
+                contract.set_token_decimals(18),
+                Err(PSP22Error::Custom(String::from("decimals locked")))
+            );
+            assert_eq!(contract.token_decimals(), 5);
+        }
+
+        #[ink::test]
+        fn transfer_to_self_keeps_balance() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.transfer(accounts.alice, 400, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 1_000);
+        }
+
+        #[ink::test]
+        fn transfer_reads_and_writes_each_balance_once() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(2_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            let callee = AccountId::from(CONTRACT);
+
+            let (reads_before, writes_before) =
+                ink::env::test::get_contract_storage_rw::<ink::env::DefaultEnvironment>(&callee);
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            let (reads_after, writes_after) =
+                ink::env::test::get_contract_storage_rw::<ink::env::DefaultEnvironment>(&callee);
+
+            // Two frozen-flag reads, the pair total and one read per balance;
+            // one write for the pair total and one per balance.
+            assert_eq!(reads_after - reads_before, 5);
+            assert_eq!(writes_after - writes_before, 3);
+        }
+
+        #[ink::test]
+        fn total_transferred_accumulates_per_pair() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.charlie, 500), Ok(()));
+
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 50, vec![]), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 25, vec![]),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.alice, 10, vec![]), Ok(()));
+
+            assert_eq!(
+                contract.total_transferred(accounts.alice, accounts.bob),
+                175
+            );
+            assert_eq!(contract.total_transferred(accounts.bob, accounts.alice), 10);
+            assert_eq!(
+                contract.total_transferred(accounts.alice, accounts.charlie),
+                0
+            );
+        }
+
+        #[ink::test]
+        fn total_transferred_counts_fees() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.set_transfer_fee_bps(100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 500, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 495);
+            assert_eq!(
+                contract.total_transferred(accounts.bob, accounts.charlie),
+                500
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_fee_mode(FeeMode::Exclusive), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 400, vec![]), Ok(()));
+            assert_eq!(
+                contract.total_transferred(accounts.bob, accounts.charlie),
+                904
+            );
+        }
+
+        #[ink::test]
+        fn transfer_with_balance_returns_remaining_sender_balance() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            let remaining = contract.transfer_with_balance(accounts.bob, 300, vec![]);
+            assert_eq!(remaining, Ok(700));
+            assert_eq!(remaining, Ok(contract.balance_of(accounts.alice)));
+            assert_eq!(
+                contract.transfer_with_balance(accounts.bob, 701, vec![]),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn approve_and_call_validates_before_calling_spender() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.approve_and_call(AccountId::from([0u8; 32]), 100, vec![]),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            assert_eq!(contract.pause(), Ok(()));
+            assert_eq!(
+                contract.approve_and_call(accounts.bob, 100, vec![]),
+                Err(PSP22Error::Custom(String::from("paused")))
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn oversized_data_is_rejected() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 500), Ok(()));
+            let data_too_large = Err(PSP22Error::Custom(String::from("data too large")));
+
+            assert_eq!(
+                contract.transfer(accounts.bob, 10, vec![0; MAX_DATA_BYTES]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.transfer(accounts.bob, 10, vec![0; MAX_DATA_BYTES + 1]),
+                data_too_large
+            );
+            assert_eq!(
+                contract.transfer_tagged(accounts.bob, 10, *b"tag0", vec![0; MAX_DATA_BYTES + 1]),
+                data_too_large
+            );
+            assert_eq!(
+                contract.approve_and_call(accounts.bob, 10, vec![0; MAX_DATA_BYTES + 1]),
+                data_too_large
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 10, vec![0; MAX_DATA_BYTES]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.transfer_from(
+                    accounts.alice,
+                    accounts.bob,
+                    10,
+                    vec![0; MAX_DATA_BYTES + 1]
+                ),
+                data_too_large
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 20);
+        }
+
+        #[ink::test]
+        fn mint_proportional_splits_and_conserves_total() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 10, vec![]), Ok(()));
+
+            assert_eq!(
+                contract.mint_proportional(
+                    vec![accounts.bob, accounts.charlie, accounts.django],
+                    vec![1, 2, 3],
+                    1_000
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 10 + 166);
+            assert_eq!(contract.balance_of(accounts.charlie), 333);
+            // 1000 * 3 / 6 = 500, plus the rounding remainder of 1.
+            assert_eq!(contract.balance_of(accounts.django), 501);
+            assert_eq!(contract.total_supply(), 101_000);
+            // The admin holds the initial supply.
+            assert_eq!(contract.holder_count(), 4);
+            // The seeding transfer, then one mint per recipient.
+            assert_eq!(token_events().count(), 4);
+
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.eve, accounts.frank], vec![1, 1], 500),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.eve), 250);
+            assert_eq!(contract.balance_of(accounts.frank), 250);
+            assert_eq!(contract.total_supply(), 101_500);
+        }
+
+        #[ink::test]
+        fn mint_proportional_rejects_invalid_input() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.mint_proportional(vec![], vec![], 100),
+                Err(PSP22Error::Custom(String::from("empty recipients")))
+            );
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.bob], vec![1, 2], 100),
+                Err(PSP22Error::Custom(String::from("length mismatch")))
+            );
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.bob], vec![0], 100),
+                Err(PSP22Error::Custom(String::from("zero weights")))
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.bob], vec![1], 100),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            assert_eq!(contract.total_supply(), 100_000);
+        }
+
+        #[ink::test]
+        fn has_ever_held_survives_spending_down_to_zero() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert!(!contract.has_ever_held(accounts.bob));
+
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            assert!(contract.has_ever_held(accounts.bob));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.alice, 100, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert!(contract.has_ever_held(accounts.bob));
+            assert!(!contract.has_ever_held(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn self_approval_rejected_unless_allowed() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            let self_approval = Err(PSP22Error::Custom(String::from("self approval")));
+
+            assert_eq!(contract.approve(accounts.alice, 100), self_approval);
+            assert_eq!(
+                contract.increase_allowance(accounts.alice, 100),
+                self_approval
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.alice), 0);
+
+            assert_eq!(contract.set_allow_self_approval(true), Ok(()));
+            assert_eq!(contract.approve(accounts.alice, 100), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn stream_accrues_over_time_and_drains_fully() {
+            let accounts = accounts_with_contract_callee();
+            let escrow = AccountId::from(CONTRACT);
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+
+            let stream_id = contract.create_stream(accounts.bob, 10, 600).unwrap();
+            assert_eq!(contract.balance_of(accounts.alice), 400);
+            assert_eq!(contract.balance_of(escrow), 600);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(40_000);
+            assert_eq!(contract.stream_withdrawable(stream_id), 300);
+            set_caller(accounts.bob);
+            assert_eq!(contract.withdraw_stream(stream_id), Ok(300));
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+            assert_eq!(contract.withdraw_stream(stream_id), Ok(0));
+
+            // Accrual is capped at the deposit.
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000_000);
+            assert_eq!(contract.withdraw_stream(stream_id), Ok(300));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+            assert_eq!(contract.balance_of(escrow), 0);
+            assert_eq!(contract.stream(stream_id), None);
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.withdraw_stream(stream_id),
+                Err(PSP22Error::Custom(String::from("stream not found")))
+            );
+        }
+
+        #[ink::test]
+        fn cancel_stream_splits_accrued_and_remainder() {
+            let accounts = accounts_with_contract_callee();
+            let escrow = AccountId::from(CONTRACT);
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(0);
+
+            let stream_id = contract.create_stream(accounts.bob, 5, 500).unwrap();
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(20_000);
+            set_caller(accounts.bob);
+            assert_eq!(contract.withdraw_stream(stream_id), Ok(100));
+            assert_eq!(
+                contract.cancel_stream(stream_id),
+                Err(PSP22Error::Custom(String::from("not stream sender")))
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30_000);
+            set_caller(accounts.alice);
+            assert_eq!(contract.cancel_stream(stream_id), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+            assert_eq!(contract.balance_of(accounts.alice), 850);
+            assert_eq!(contract.balance_of(escrow), 0);
+            assert_eq!(contract.stream(stream_id), None);
+        }
+
+        #[ink::test]
+        fn derive_treasury_is_deterministic_and_mintable() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            let maintenance = contract.derive_treasury(*b"maintain");
+
+            assert_eq!(contract.derive_treasury(*b"maintain"), maintenance);
+            assert_ne!(contract.derive_treasury(*b"reserves"), maintenance);
+
+            assert_eq!(contract.mint_to_treasury(*b"maintain", 5_000), Ok(()));
+            assert_eq!(contract.balance_of(maintenance), 5_000);
+            assert_eq!(contract.total_supply(), 105_000);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.mint_to_treasury(*b"maintain", 1),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+        }
+
+        #[ink::test]
+        fn transfer_to_token_contract_is_rejected() {
+            let accounts = accounts_with_contract_callee();
+            let token = AccountId::from(CONTRACT);
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 500), Ok(()));
+            let rejected = Err(PSP22Error::Custom(String::from(
+                "transfer to token contract",
+            )));
+
+            assert_eq!(contract.transfer(token, 10, vec![]), rejected);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, token, 10, vec![]),
+                rejected
+            );
+            assert_eq!(contract.balance_of(token), 0);
+            assert_eq!(contract.balance_of(accounts.alice), 1_000);
+        }
+
+        #[ink::test]
+        fn fee_recipient_changes_in_two_steps() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.fee_recipient(), accounts.alice);
+
+            assert_eq!(contract.propose_fee_recipient(accounts.bob), Ok(()));
+            assert_eq!(contract.pending_fee_recipient(), Some(accounts.bob));
+            assert_eq!(contract.fee_recipient(), accounts.alice);
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.accept_fee_recipient(),
+                Err(PSP22Error::Custom(String::from(
+                    "not pending fee recipient"
+                )))
+            );
+            assert_eq!(
+                contract.propose_fee_recipient(accounts.charlie),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_fee_recipient(), Ok(()));
+            assert_eq!(contract.fee_recipient(), accounts.bob);
+            assert_eq!(contract.pending_fee_recipient(), None);
+
+            let events: Vec<_> = token_events().collect();
+            assert_eq!(events.len(), 2);
+            assert!(matches!(
+                decode_event(&events[0]),
+                Event::FeeRecipientChangeProposed(_)
+            ));
+            match decode_event(&events[1]) {
+                Event::FeeRecipientChanged(FeeRecipientChanged {
+                    old_recipient,
+                    new_recipient,
+                }) => {
+                    assert_eq!(old_recipient, accounts.alice);
+                    assert_eq!(new_recipient, accounts.bob);
+                }
+                _ => panic!("expected FeeRecipientChanged event"),
+            }
+        }
+
+        #[ink::test]
+        fn zero_fee_recipient_is_rejected() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.propose_fee_recipient(AccountId::from([0u8; 32])),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            assert_eq!(contract.pending_fee_recipient(), None);
+        }
+
+        #[ink::test]
+        fn transfer_from_operator_bypasses_numeric_allowance() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 1, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 101, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 40, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 60);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_operator(accounts.bob, true), Ok(()));
+            assert!(contract.is_operator(accounts.alice, accounts.bob));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 500, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 60);
+            assert_eq!(contract.balance_of(accounts.alice), 460);
+            assert_eq!(contract.balance_of(accounts.django), 540);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_operator(accounts.bob, false), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 61, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+
+        #[ink::test]
+        fn burn_and_redeem_pays_proportional_reserve() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 250, vec![]), Ok(()));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(4_000);
+            assert_eq!(contract.fund_reserve(), Ok(()));
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(0);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from(CONTRACT),
+                4_000,
+            );
+            assert_eq!(contract.native_reserve(), 4_000);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.fund_reserve(),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            assert_eq!(
+                contract.burn_and_redeem(251),
+                Err(PSP22Error::InsufficientBalance)
+            );
+
+            let bob_native =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob)
+                    .unwrap();
+            assert_eq!(contract.burn_and_redeem(100), Ok(400));
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+            assert_eq!(contract.total_supply(), 900);
+            assert_eq!(contract.native_reserve(), 3_600);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.bob),
+                Ok(bob_native + 400)
+            );
+            assert_eq!(contract.burn_and_redeem(0), Ok(0));
+        }
+
+        #[ink::test]
+        fn accept_admin_within_grace_period() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_admin_transfer_grace_secs(60), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(contract.propose_admin(accounts.bob), Ok(()));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.accept_admin(),
+                Err(PSP22Error::Custom(String::from("not pending admin")))
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(61_000);
+            set_caller(accounts.bob);
+            assert_eq!(contract.pending_admin(), Some(accounts.bob));
+            assert_eq!(contract.accept_admin(), Ok(()));
+            assert_eq!(contract.admin, accounts.bob);
+            assert_eq!(contract.pending_admin(), None);
+            assert_eq!(contract.kyc_admin(), accounts.bob);
+
+            // A KYC admin handed to someone else stays put.
+            assert_eq!(contract.set_kyc_admin(accounts.django), Ok(()));
+            assert_eq!(contract.propose_admin(accounts.charlie), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(contract.accept_admin(), Ok(()));
+            assert_eq!(contract.kyc_admin(), accounts.django);
+        }
+
+        #[ink::test]
+        fn accept_admin_rejects_lapsed_proposal() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_admin_transfer_grace_secs(60), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(contract.propose_admin(accounts.bob), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(61_001);
+            set_caller(accounts.bob);
+            assert_eq!(contract.pending_admin(), None);
+            assert_eq!(
+                contract.accept_admin(),
+                Err(PSP22Error::Custom(String::from("transfer expired")))
+            );
+            assert_eq!(contract.admin, accounts.alice);
+        }
+
+        #[ink::test]
+        fn log_admin_precheck_emits_only_when_enabled() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.log_admin_precheck(*b"paus"),
+                Some(REASON_NOT_ADMIN)
+            );
+            assert_eq!(token_events().count(), 0);
+            assert_eq!(
+                contract.set_audit_failed_ops(true),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_audit_failed_ops(true), Ok(()));
+            assert_eq!(contract.log_admin_precheck(*b"paus"), None);
+            assert_eq!(token_events().count(), 0);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.log_admin_precheck(*b"paus"),
+                Some(REASON_NOT_ADMIN)
+            );
+            let events: Vec<_> = token_events().collect();
+            assert_eq!(events.len(), 1);
+            match decode_event(&events[0]) {
+                Event::OperationFailed(OperationFailed {
+                    op,
+                    caller,
+                    reason_code,
+                }) => {
+                    assert_eq!(op, *b"paus");
+                    assert_eq!(caller, accounts.bob);
+                    assert_eq!(reason_code, REASON_NOT_ADMIN);
+                }
+                _ => panic!("expected OperationFailed"),
+            }
+        }
+
+        #[ink::test]
+        fn supply_invariant_holds_across_mixed_operations() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(10_000, accounts.alice, 5u8);
+            assert_eq!(contract.balance_of(accounts.alice), 10_000);
+            assert!(contract.check_supply_invariant());
+
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 500), Ok(()));
+            assert_eq!(contract.mint_to_treasury(*b"reserves", 2_000), Ok(()));
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.django, accounts.eve], vec![1, 3], 400),
+                Ok(())
+            );
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 500, vec![]),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.burn_and_redeem(300), Ok(0));
+
+            assert_eq!(contract.total_supply(), 12_100);
+            assert_eq!(contract.balance_sum(), 12_100);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn supply_invariant_detects_corrupted_balances() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(10_000, accounts.alice, 5u8);
+
+            // Writes a balance without minting, as a faulty code path would.
+            let shares = contract.to_shares(1);
+            contract.write_shares(accounts.bob, 0, shares);
+            assert!(!contract.check_supply_invariant());
+            assert_eq!(contract.balance_sum(), contract.total_supply() + 1);
+        }
+
+        #[ink::test]
+        fn transfer_with_native_fee_forwards_fee_to_recipient() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_native_fee_amount(50), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 400, vec![]), Ok(()));
+
+            set_caller(accounts.bob);
+            ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(
+                AccountId::from(CONTRACT),
+                50,
+            );
+            let alice_native =
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice)
+                    .unwrap();
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(50);
+            assert_eq!(
+                contract.transfer_with_native_fee(accounts.charlie, 100, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 300);
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+            assert_eq!(
+                ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(accounts.alice),
+                Ok(alice_native + 50)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_with_native_fee_rejects_underfunded_call() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_native_fee_amount(50), Ok(()));
+
+            ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(49);
+            assert_eq!(
+                contract.transfer_with_native_fee(accounts.bob, 100, vec![]),
+                Err(PSP22Error::Custom(String::from("insufficient native fee")))
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(token_events().count(), 0);
+        }
+
+        #[ink::test]
+        fn transfer_fee_skips_exempt_accounts() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 10_000, vec![]), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 10_000, vec![]), Ok(()));
+            assert_eq!(contract.set_transfer_fee_bps(100), Ok(()));
+            assert_eq!(contract.set_fee_exempt(accounts.charlie, true), Ok(()));
+
+            // Non-exempt: 1% of 5_000 goes to the fee recipient.
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.django, 5_000, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 5_000);
+            assert_eq!(contract.balance_of(accounts.django), 4_950);
+            assert_eq!(contract.balance_of(accounts.alice), 80_050);
+
+            // Exempt sender.
+            set_caller(accounts.charlie);
+            assert_eq!(contract.transfer(accounts.django, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.django), 5_950);
+
+            // Exempt recipient, via transfer_from.
+            set_caller(accounts.bob);
+            assert_eq!(contract.approve(accounts.eve, 2_000), Ok(()));
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.transfer_from(accounts.bob, accounts.charlie, 2_000, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.charlie), 11_000);
+            assert_eq!(contract.balance_of(accounts.alice), 80_050);
+            assert_eq!(contract.allowance(accounts.bob, accounts.eve), 0);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_fee_exempt(accounts.charlie, false), Ok(()));
+            assert!(!contract.is_fee_exempt(accounts.charlie));
+            assert!(contract.is_fee_exempt(accounts.alice));
+        }
+
+        #[ink::test]
+        fn pause_until_lapses_at_deadline() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(
+                contract.pause_until(1_000),
+                Err(PSP22Error::Custom(String::from("deadline passed")))
+            );
+            assert_eq!(contract.pause_until(5_000), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(4_999);
+            assert!(contract.paused());
+            assert_eq!(
+                contract.transfer(accounts.bob, 10, vec![]),
+                Err(PSP22Error::Custom(String::from("paused")))
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(5_000);
+            assert!(!contract.paused());
+            assert_eq!(contract.transfer(accounts.bob, 10, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 10);
+
+            // A plain pause has no deadline.
+            assert_eq!(contract.pause(), Ok(()));
+            assert_eq!(contract.pause_deadline(), None);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000_000);
+            assert!(contract.paused());
+        }
+
+        #[ink::test]
+        fn increase_allowance_creates_allowance_from_scratch() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert!(!contract.allowances.contains((accounts.alice, accounts.bob)));
+
+            assert_eq!(contract.increase_allowance(accounts.bob, 250), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 250);
+            assert_eq!(
+                contract.increase_allowance(accounts.bob, Balance::MAX),
+                Err(PSP22Error::Custom(String::from("overflow")))
+            );
+        }
+
+        #[ink::test]
+        fn zero_address_allowance_changes_leave_storage_untouched() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            let zero = AccountId::from([0u8; 32]);
+            let callee = AccountId::from(CONTRACT);
+
+            let before =
+                ink::env::test::get_contract_storage_rw::<ink::env::DefaultEnvironment>(&callee);
+            assert_eq!(
+                contract.increase_allowance(zero, 10),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            assert_eq!(
+                contract.decrease_allowance(zero, 10),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            set_caller(zero);
+            assert_eq!(
+                contract.increase_allowance(accounts.bob, 10),
+                Err(PSP22Error::ZeroSenderAddress)
+            );
+            assert_eq!(
+                contract.decrease_allowance(accounts.bob, 10),
+                Err(PSP22Error::ZeroSenderAddress)
+            );
+            let after =
+                ink::env::test::get_contract_storage_rw::<ink::env::DefaultEnvironment>(&callee);
+
+            assert_eq!(after, before);
+            assert_eq!(ink::env::test::recorded_events().count(), 0);
+        }
+
+        #[ink::test]
+        fn claim_merkle_verifies_proof_once_per_leaf() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            let leaves = [
+                RedToken::merkle_leaf(accounts.bob, 100),
+                RedToken::merkle_leaf(accounts.charlie, 200),
+                RedToken::merkle_leaf(accounts.django, 300),
+                RedToken::merkle_leaf(accounts.eve, 400),
+            ];
+            let left = RedToken::merkle_parent(&leaves[0], &leaves[1]);
+            let right = RedToken::merkle_parent(&leaves[2], &leaves[3]);
+            let root = RedToken::merkle_parent(&left, &right);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.claim_merkle(100, vec![leaves[1], right]),
+                Err(PSP22Error::Custom(String::from("no merkle root")))
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_merkle_root(root), Ok(()));
+
+            set_caller(accounts.django);
+            assert_eq!(contract.claim_merkle(300, vec![leaves[3], left]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.django), 300);
+            assert!(contract.is_merkle_claimed(accounts.django, 300));
+            assert_eq!(
+                contract.claim_merkle(300, vec![leaves[3], left]),
+                Err(PSP22Error::Custom(String::from("already claimed")))
+            );
+
+            // Wrong amount, and a proof belonging to someone else.
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.claim_merkle(101, vec![leaves[1], right]),
+                Err(PSP22Error::Custom(String::from("invalid proof")))
+            );
+            set_caller(accounts.frank);
+            assert_eq!(
+                contract.claim_merkle(100, vec![leaves[1], right]),
+                Err(PSP22Error::Custom(String::from("invalid proof")))
+            );
+            assert_eq!(contract.total_supply(), 100_300);
+        }
+
+        #[ink::test]
+        fn fee_mode_inclusive_deducts_and_exclusive_adds() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_transfer_fee_bps(100), Ok(()));
+            assert_eq!(contract.fee_mode(), FeeMode::Inclusive);
+            // The admin is the fee recipient, so this transfer is free.
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 500, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 500);
+            assert_eq!(contract.balance_of(accounts.charlie), 495);
+            assert_eq!(contract.balance_of(accounts.alice), 99_005);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_fee_mode(FeeMode::Exclusive), Ok(()));
+
+            // 500 plus a fee of 5 exceeds the remaining balance.
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.charlie, 500, vec![]),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(contract.transfer(accounts.charlie, 400, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 96);
+            assert_eq!(contract.balance_of(accounts.charlie), 895);
+            assert_eq!(contract.balance_of(accounts.alice), 99_009);
+
+            // A spender's allowance must cover the fee too.
+            set_caller(accounts.charlie);
+            assert_eq!(contract.approve(accounts.django, 202), Ok(()));
+            set_caller(accounts.django);
+            assert_eq!(
+                contract.transfer_from(accounts.charlie, accounts.eve, 201, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                contract.transfer_from(accounts.charlie, accounts.eve, 200, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.eve), 200);
+            assert_eq!(contract.balance_of(accounts.charlie), 693);
+            assert_eq!(contract.allowance(accounts.charlie, accounts.django), 0);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn contract_balance_reports_own_account() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.contract_balance(), 0);
+
+            let own = AccountId::from(CONTRACT);
+            assert_eq!(contract.mint_proportional(vec![own], vec![1], 750), Ok(()));
+            assert_eq!(contract.contract_balance(), 750);
+            assert_eq!(contract.contract_balance(), contract.balance_of(own));
+        }
+
+        #[ink::test]
+        fn transfer_display_scales_by_decimals() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.transfer_display(accounts.bob, 2, 0, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 200_000);
+
+            assert_eq!(
+                contract.transfer_display(accounts.charlie, 1, 50_000, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.charlie), 150_000);
+            assert_eq!(contract.format_amount(150_000), (1, 50_000));
+
+            assert_eq!(
+                contract.transfer_display(accounts.charlie, 0, 100_000, vec![]),
+                Err(PSP22Error::Custom(String::from("fraction too large")))
+            );
+            assert_eq!(contract.balance_of(accounts.alice), 650_000);
+        }
+
+        #[ink::test]
+        fn lockup_allows_only_allowlisted_senders() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.transfer(accounts.charlie, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.set_lockup_end(10_000), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.django, 10, vec![]),
+                Err(PSP22Error::Custom(String::from("locked up")))
+            );
+            assert_eq!(
+                contract.set_transfer_allowed(accounts.bob, true),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_transfer_allowed(accounts.bob, true), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.django, 10, vec![]), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            set_caller(accounts.charlie);
+            assert_eq!(contract.transfer(accounts.django, 10, vec![]), Ok(()));
+        }
+
+        #[ink::test]
+        fn transfer_allowlist_updates_emit_events() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.set_transfer_allowed(accounts.bob, true), Ok(()));
+            assert_eq!(
+                contract.set_transfer_allowed_batch(vec![accounts.charlie, accounts.django], true),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_transfer_allowed_batch(vec![accounts.bob], false),
+                Ok(())
+            );
+            assert!(!contract.is_transfer_allowed(accounts.bob));
+            assert!(contract.is_transfer_allowed(accounts.charlie));
+            assert_eq!(
+                contract.set_transfer_allowed_batch(vec![accounts.eve; MAX_BATCH_SIZE + 1], true),
+                Err(PSP22Error::Custom(String::from("batch too large")))
+            );
+
+            let updates: Vec<_> = token_events()
+                .map(|event| match decode_event(&event) {
+                    Event::TransferAllowlistUpdated(TransferAllowlistUpdated {
+                        account,
+                        allowed,
+                    }) => (account, allowed),
+                    _ => panic!("expected TransferAllowlistUpdated"),
+                })
+                .collect();
+            assert_eq!(
+                updates,
+                vec![
+                    (accounts.bob, true),
+                    (accounts.charlie, true),
+                    (accounts.django, true),
+                    (accounts.bob, false),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn min_approval_rejects_small_nonzero_allowances() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_min_approval(100), Ok(()));
+
+            assert_eq!(
+                contract.approve(accounts.bob, 99),
+                Err(PSP22Error::Custom(String::from("approval too small")))
+            );
+            assert_eq!(
+                contract.increase_allowance(accounts.bob, 50),
+                Err(PSP22Error::Custom(String::from("approval too small")))
+            );
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.bob, 1), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 101);
+
+            assert_eq!(contract.approve(accounts.bob, 0), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_with_receipt_matches_post_state() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+
+            let receipt = contract
+                .transfer_with_receipt(accounts.bob, 2_500, vec![])
+                .unwrap();
+            assert_eq!(
+                receipt,
+                TransferReceipt {
+                    from: accounts.alice,
+                    to: accounts.bob,
+                    value: 2_500,
+                    block: ink::env::block_number::<ink::env::DefaultEnvironment>(),
+                    new_sender_balance: contract.balance_of(accounts.alice),
+                    new_recipient_balance: contract.balance_of(accounts.bob),
+                }
+            );
+            assert_eq!(receipt.block, 1);
+            assert_eq!(receipt.new_sender_balance, 97_500);
+            assert_eq!(receipt.new_recipient_balance, 2_500);
+        }
+
+        #[ink::test]
+        fn transfer_fee_split_conserves_value() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(Balance::MAX / 2, accounts.alice, 5u8);
+
+            // xorshift64, so the sequence is random-looking but reproducible.
+            let mut seed = 0x9E37_79B9_7F4A_7C15u64;
+            let mut next = move || {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed
+            };
+
+            for _ in 0..200 {
+                let value = Balance::from(next() % 1_000_000_000_007);
+                let fee_bps = (next() % (BPS_DENOMINATOR as u64 + 1)) as u16;
+                let fee_mode = if next() % 2 == 0 {
+                    FeeMode::Inclusive
+                } else {
+                    FeeMode::Exclusive
+                };
+
+                set_caller(accounts.alice);
+                assert_eq!(contract.set_transfer_fee_bps(fee_bps), Ok(()));
+                assert_eq!(contract.set_fee_mode(fee_mode), Ok(()));
+                assert_eq!(contract.transfer(accounts.bob, value * 2, vec![]), Ok(()));
+
+                let bob_before = contract.balance_of(accounts.bob);
+                let charlie_before = contract.balance_of(accounts.charlie);
+                let alice_before = contract.balance_of(accounts.alice);
+                let events_before = ink::env::test::recorded_events().count();
+
+                set_caller(accounts.bob);
+                assert_eq!(contract.transfer(accounts.charlie, value, vec![]), Ok(()));
+
+                let sent = bob_before - contract.balance_of(accounts.bob);
+                let received = contract.balance_of(accounts.charlie) - charlie_before;
+                let fee = contract.balance_of(accounts.alice) - alice_before;
+                assert_eq!(received + fee, sent);
+                assert_eq!(
+                    if fee_mode == FeeMode::Inclusive {
+                        sent
+                    } else {
+                        received
+                    },
+                    value
+                );
+
+                // One `Transfer` to the recipient, then one to the fee recipient if
+                // a fee was taken, each for exactly what that side received.
+                let emitted: Vec<_> = ink::env::test::recorded_events()
+                    .skip(events_before)
+                    .map(|event| match decode_event(&event) {
+                        Event::Transfer(Transfer { to, value, .. }) => (to, value),
+                        _ => panic!("expected Transfer"),
+                    })
+                    .collect();
+                let mut expected = vec![(Some(accounts.charlie), received)];
+                if fee > 0 {
+                    expected.push((Some(accounts.alice), fee));
+                }
+                assert_eq!(emitted, expected);
+                assert!(contract.check_supply_invariant());
+            }
+        }
+
+        #[ink::test]
+        fn daily_limit_counts_stream_deposits() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_daily_limit(Some(1_000)), Ok(()));
+
+            assert_eq!(contract.create_stream(accounts.bob, 1, 800), Ok(0));
+            assert_eq!(contract.daily_volume(accounts.alice), 800);
+            assert_eq!(
+                contract.create_stream(accounts.bob, 1, 201),
+                Err(PSP22Error::Custom(String::from("daily limit exceeded")))
+            );
+            assert_eq!(contract.transfer(accounts.bob, 200, vec![]), Ok(()));
+        }
+
+        #[ink::test]
+        fn daily_limit_resets_at_day_boundary() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_daily_limit(Some(1_000)), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MS_PER_DAY - 10);
+
+            assert_eq!(contract.transfer(accounts.bob, 600, vec![]), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 1_000), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 400, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.daily_volume(accounts.alice), 1_000);
+
+            set_caller(accounts.alice);
+            assert_eq!(
+                contract.transfer(accounts.bob, 1, vec![]),
+                Err(PSP22Error::Custom(String::from("daily limit exceeded")))
+            );
+            // Other accounts have their own allowance for the day.
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.django, 600, vec![]), Ok(()));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(MS_PER_DAY);
+            set_caller(accounts.alice);
+            assert_eq!(contract.daily_volume(accounts.alice), 0);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 98_000);
+        }
+
+        #[ink::test]
+        fn can_transfer_from_reports_the_error_without_mutating() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 500), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.can_transfer_from(accounts.alice, accounts.charlie, 501),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(
+                contract.can_transfer_from(accounts.alice, accounts.charlie, 500),
+                Ok(())
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.transfer(accounts.django, 700, vec![]), Ok(()));
+            let events = ink::env::test::recorded_events().count();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.can_transfer_from(accounts.alice, accounts.charlie, 400),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(
+                contract.can_transfer_from(accounts.alice, accounts.charlie, 300),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 500);
+            assert_eq!(contract.balance_of(accounts.charlie), 0);
+            assert_eq!(ink::env::test::recorded_events().count(), events);
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.pause(), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.can_transfer_from(accounts.alice, accounts.charlie, 300),
+                Err(PSP22Error::Custom(String::from("paused")))
+            );
+        }
+
+        #[ink::test]
+        fn admin_actions_are_counted_and_logged() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+
+            assert_eq!(contract.pause(), Ok(()));
+            assert_eq!(contract.unpause(), Ok(()));
+            assert_eq!(contract.mint_to_treasury(*b"reserves", 10), Ok(()));
+            assert_eq!(contract.set_transfer_fee_bps(25), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 10, vec![]), Ok(()));
+            assert_eq!(contract.admin_action_count(), 4);
+
+            let actions: Vec<_> = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::AdminAction(AdminAction {
+                        action_code,
+                        caller,
+                        block,
+                    }) => {
+                        assert_eq!(caller, accounts.alice);
+                        assert_eq!(block, 1);
+                        Some(action_code)
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                actions,
+                vec![
+                    ink::selector_bytes!("pause"),
+                    ink::selector_bytes!("unpause"),
+                    ink::selector_bytes!("mint_to_treasury"),
+                    ink::selector_bytes!("set_transfer_fee_bps"),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_exact_allowance_leaves_zero() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 100, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.charlie), 100);
+
+            let events: Vec<_> = ink::env::test::recorded_events().collect();
+            match decode_event(events.last().unwrap()) {
+                Event::Approval(Approval {
+                    owner,
+                    spender,
+                    value,
+                    ..
+                }) => {
+                    assert_eq!(owner, accounts.alice);
+                    assert_eq!(spender, accounts.bob);
+                    assert_eq!(value, 0);
+                }
+                _ => panic!("expected Approval"),
+            }
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 1, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+
+        #[ink::test]
+        fn try_from_int_errors_convert_to_overflow() {
+            fn to_bps(value: u128) -> PSP22Result<u16> {
+                Ok(u16::try_from(value)?)
+            }
+
+            assert_eq!(to_bps(10_000), Ok(10_000));
+            assert_eq!(
+                to_bps(u128::from(u16::MAX) + 1),
+                Err(PSP22Error::Custom(String::from("overflow")))
+            );
+        }
+
+        #[ink::test]
+        fn last_approval_records_value_and_block() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.last_approval(accounts.alice, accounts.bob), None);
+
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(
+                contract.last_approval(accounts.alice, accounts.bob),
+                Some((100, 0))
+            );
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(contract.increase_allowance(accounts.bob, 50), Ok(()));
+            assert_eq!(
+                contract.last_approval(accounts.alice, accounts.bob),
+                Some((150, 2))
+            );
+
+            ink::env::test::advance_block::<ink::env::DefaultEnvironment>();
+            assert_eq!(contract.decrease_allowance(accounts.bob, 30), Ok(()));
+            assert_eq!(
+                contract.last_approval(accounts.alice, accounts.bob),
+                Some((120, 3))
+            );
+
+            // Spending the allowance leaves the log at the last grant.
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 20, vec![]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.last_approval(accounts.alice, accounts.bob),
+                Some((120, 3))
+            );
+        }
+
+        #[ink::test]
+        fn freeze_metadata_locks_name_and_symbol() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.set_token_name(String::from("Real Estate DAO Token")),
+                Ok(())
+            );
+            assert_eq!(contract.set_token_symbol(String::from("REDT")), Ok(()));
+            assert_eq!(
+                PSP22Metadata::token_name(&contract),
+                Some(String::from("Real Estate DAO Token"))
+            );
+            assert_eq!(
+                PSP22Metadata::token_symbol(&contract),
+                Some(String::from("REDT"))
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.freeze_metadata(),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.freeze_metadata(), Ok(()));
+            assert!(contract.metadata_frozen());
+
+            assert_eq!(
+                contract.set_token_name(String::from("Other")),
+                Err(PSP22Error::Custom(String::from("metadata frozen")))
+            );
+            assert_eq!(
+                contract.set_token_symbol(String::from("OTH")),
+                Err(PSP22Error::Custom(String::from("metadata frozen")))
+            );
+            assert_eq!(
+                PSP22Metadata::token_symbol(&contract),
+                Some(String::from("REDT"))
+            );
+        }
+
+        #[ink::test]
+        fn zero_address_transfers_burn_only_when_enabled() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            let zero = AccountId::from([0u8; 32]);
+            assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+
+            assert_eq!(
+                contract.transfer(zero, 100, vec![]),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, zero, 100, vec![]),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_zero_address_burns(true), Ok(()));
+            assert_eq!(contract.set_transfer_fee_bps(100), Ok(()));
+            assert_eq!(contract.transfer(zero, 100, vec![]), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, zero, 300, vec![]),
+                Ok(())
+            );
+
+            assert_eq!(contract.balance_of(accounts.alice), 600);
+            assert_eq!(contract.balance_of(zero), 0);
+            assert_eq!(contract.total_supply(), 600);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert!(contract.check_supply_invariant());
+
+            let burns = token_events()
+                .filter(|event| {
+                    matches!(
+                        decode_event(event),
+                        Event::Transfer(Transfer { to: None, .. })
+                    )
+                })
+                .count();
+            assert_eq!(burns, 2);
+        }
+
+        #[ink::test]
+        fn allowance_batches_apply_each_entry() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract
+                    .increase_allowance_batch(vec![accounts.bob, accounts.charlie], vec![100, 200]),
+                Ok(())
+            );
+            assert_eq!(
+                contract
+                    .decrease_allowance_batch(vec![accounts.bob, accounts.charlie], vec![40, 200]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 60);
+            assert_eq!(contract.allowance(accounts.alice, accounts.charlie), 0);
+
+            let approvals: Vec<_> = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::Approval(Approval { spender, value, .. }) => Some((spender, value)),
+                    Event::AllowanceChanged(_) => None,
+                    _ => panic!("expected Approval"),
+                })
+                .collect();
+            assert_eq!(
+                approvals,
+                vec![
+                    (accounts.bob, 100),
+                    (accounts.charlie, 200),
+                    (accounts.bob, 60),
+                    (accounts.charlie, 0),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn allowance_batches_reject_bad_input() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.increase_allowance_batch(vec![accounts.bob, accounts.charlie], vec![1]),
+                Err(PSP22Error::Custom(String::from("length mismatch")))
+            );
+            assert_eq!(
+                contract.decrease_allowance_batch(vec![accounts.bob], vec![1, 2]),
+                Err(PSP22Error::Custom(String::from("length mismatch")))
+            );
+            assert_eq!(
+                contract.increase_allowance_batch(
+                    vec![accounts.bob; MAX_BATCH_SIZE + 1],
+                    vec![1; MAX_BATCH_SIZE + 1]
+                ),
+                Err(PSP22Error::Custom(String::from("batch too large")))
+            );
+
+            assert_eq!(contract.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(
+                contract
+                    .decrease_allowance_batch(vec![accounts.bob, accounts.charlie], vec![10, 1]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+
+        #[ink::test]
+        fn lifetime_mint_and_burn_totals_accumulate() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.total_minted(), 1_000);
+            assert_eq!(contract.total_burned(), 0);
+
+            assert_eq!(contract.mint_to_treasury(*b"reserves", 500), Ok(()));
+            assert_eq!(
+                contract.mint_proportional(vec![accounts.bob], vec![1], 250),
+                Ok(())
+            );
+            assert_eq!(contract.set_zero_address_burns(true), Ok(()));
+            assert_eq!(
+                contract.transfer(AccountId::from([0u8; 32]), 300, vec![]),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.burn_and_redeem(50), Ok(0));
+
+            assert_eq!(contract.total_minted(), 1_750);
+            assert_eq!(contract.total_burned(), 350);
+            assert_eq!(
+                contract.total_supply(),
+                contract.total_minted() - contract.total_burned()
+            );
+        }
+
+        // Allowlisted contracts are asked through `PSP22Receiver`, which the
+        // off-chain environment can't call; see the e2e tests.
+        #[ink::test]
+        fn only_allowlisted_contracts_receive_tokens() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            ink::env::test::set_contract::<ink::env::DefaultEnvironment>(accounts.frank);
+
+            assert!(matches!(
+                contract.transfer(accounts.frank, 100, vec![]),
+                Err(PSP22Error::SafeTransferCheckFailed(_))
+            ));
+
+            assert_eq!(contract.approve(accounts.bob, 200), Ok(()));
+            set_caller(accounts.bob);
+            assert!(matches!(
+                contract.transfer_from(accounts.alice, accounts.frank, 100, vec![]),
+                Err(PSP22Error::SafeTransferCheckFailed(_))
+            ));
+            assert_eq!(contract.balance_of(accounts.frank), 0);
+        }
+
+        #[ink::test]
+        fn approve_with_data_emits_data() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(
+                contract.approve_with_data(accounts.bob, 300, vec![1, 2, 3]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 300);
+
+            let events: Vec<_> = token_events().collect();
+            match decode_event(events.last().unwrap()) {
+                Event::Approval(Approval {
+                    owner,
+                    spender,
+                    value,
+                    data,
+                }) => {
+                    assert_eq!(owner, accounts.alice);
+                    assert_eq!(spender, accounts.bob);
+                    assert_eq!(value, 300);
+                    assert_eq!(data, vec![1, 2, 3]);
+                }
+                _ => panic!("expected Approval"),
+            }
+        }
+
+        #[ink::test]
+        fn allowances_clamp_to_balance_when_enabled() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.approve(accounts.bob, 5_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 5_000);
+
+            assert_eq!(contract.set_cap_allowance_to_balance(true), Ok(()));
+            assert_eq!(contract.approve(accounts.bob, 5_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+            assert_eq!(contract.approve(accounts.bob, 400), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.bob, 5_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+
+            // Existing allowances are not shrunk when the balance drops, not even by
+            // an increase.
+            assert_eq!(contract.transfer(accounts.charlie, 600, vec![]), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+            assert_eq!(contract.increase_allowance(accounts.bob, 1), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 1_000);
+
+            // The minimum applies to what is asked for, not to the clamped result.
+            assert_eq!(contract.set_min_approval(500), Ok(()));
+            assert_eq!(contract.approve(accounts.django, 2_000), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.django), 400);
+            assert_eq!(
+                contract.approve(accounts.django, 100),
+                Err(PSP22Error::Custom(String::from("approval too small")))
+            );
+        }
+
+        #[ink::test]
+        fn privileged_messages_reject_non_admins() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            set_caller(accounts.bob);
+
+            let results = [
+                contract.set_price(1),
+                contract.set_price_decimals(1),
+                contract.set_token_decimals(1),
+                contract.set_token_name(String::from("X")),
+                contract.set_token_symbol(String::from("X")),
+                contract.freeze_metadata(),
+                contract.set_hide_metadata(true),
+                contract.set_max_allowance(1, true),
+                contract.set_cap_allowance_to_balance(true),
+                contract.set_min_approval(1),
+                contract.set_daily_limit(Some(1)),
+                contract.set_zero_address_burns(true),
+                contract.set_allow_self_approval(true),
+                contract.set_audit_failed_ops(true),
+                contract.pause(),
+                contract.pause_until(1),
+                contract.unpause(),
+                contract.set_native_fee_amount(1),
+                contract.set_swap_fee_bps(1),
+                contract.set_transfer_fee_bps(1),
+                contract.set_fee_mode(FeeMode::Exclusive),
+                contract.set_flash_fee_bps(1),
+                contract.set_fee_exempt(accounts.bob, true),
+                contract.mint_proportional(vec![accounts.bob], vec![1], 1),
+                contract.mint_to_treasury([0u8; 8], 1),
+                contract.set_merkle_root([0u8; 32]),
+                contract.fund_reserve(),
+                contract.set_admin_transfer_grace_secs(1),
+                contract.propose_admin(accounts.bob),
+                contract.propose_fee_recipient(accounts.bob),
+                contract.set_lockup_end(1),
+                contract.set_transfer_allowed_batch(vec![accounts.bob], true),
+                contract.set_recipient_contract_allowed(accounts.bob, true),
+                contract.grant_role(1, accounts.bob),
+                contract.revoke_role(1, accounts.bob),
+                contract.set_kyc_required(true),
+                contract.set_kyc_admin(accounts.bob),
+                contract.rebase(2, 1),
+                contract.set_require_whole_units(true),
+                contract.set_require_zero_before_reapprove(true),
+                contract.set_saturating_decrease(true),
+                contract.set_max_approvals_per_owner(1),
+                contract.set_guardian(accounts.bob),
+                contract.mint_vesting(accounts.bob, 1, 0, 1),
+                contract.verify_post_upgrade(),
+                contract
+                    .instantiate_child(Hash::default(), 1, 5u8, [0u8; 32])
+                    .map(|_| ()),
+                contract.freeze_all(String::from("hold")),
+                contract.unfreeze_all(),
+                contract.schedule_recovery(accounts.bob, accounts.charlie),
+                contract.cancel_recovery(accounts.bob),
+                contract.recover_account(accounts.bob, accounts.charlie, vec![]),
+            ];
+            for result in results {
+                assert_eq!(result, Err(PSP22Error::Custom(String::from("not admin"))));
+            }
+            assert_eq!(contract.admin_action_count(), 0);
+        }
+
+        #[ink::test]
+        fn hide_metadata_hides_name_and_symbol() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            let name = contract.token_name();
+            let symbol = contract.token_symbol();
+            assert!(name.is_some() && symbol.is_some());
+
+            assert_eq!(contract.set_hide_metadata(true), Ok(()));
+            assert_eq!(contract.token_name(), None);
+            assert_eq!(contract.token_symbol(), None);
+            assert_eq!(contract.token_decimals(), 5);
+
+            assert_eq!(contract.set_hide_metadata(false), Ok(()));
+            assert_eq!(contract.token_name(), name);
+            assert_eq!(contract.token_symbol(), symbol);
+        }
+
+        #[ink::test]
+        fn flash_loan_fee_goes_to_fee_recipient() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_flash_fee_bps(50), Ok(()));
+            assert_eq!(contract.flash_fee(10_000), Ok(50));
+            assert_eq!(
+                contract.set_flash_fee_bps(10_001),
+                Err(PSP22Error::Custom(String::from("fee too high")))
+            );
+
+            // The borrower starts with enough to cover the fee; the callback itself
+            // can't run off-chain, so the loan is opened and settled directly.
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            let (minted, burned) = (contract.total_minted(), contract.total_burned());
+            let events_before = ink::env::test::recorded_events().count();
+            assert_eq!(contract.open_flash_loan(accounts.bob, 10_000), Ok(50));
+            assert!(contract.flash_loan_active);
+            let active = || PSP22Error::Custom(String::from("flash loan active"));
+            assert_eq!(contract.open_flash_loan(accounts.bob, 1), Err(active()));
+            assert_eq!(contract.flash_loan(accounts.bob, 1, vec![]), Err(active()));
+            assert_eq!(contract.settle_flash_loan(accounts.bob, 10_000, 50), Ok(()));
+            assert!(!contract.flash_loan_active);
+
+            let transfers: Vec<_> = ink::env::test::recorded_events()
+                .skip(events_before)
+                .map(|event| match decode_event(&event) {
+                    Event::Transfer(Transfer {
+                        from, to, value, ..
+                    }) => (from, to, value),
+                    _ => panic!("expected Transfer"),
+                })
+                .collect();
+            assert_eq!(
+                transfers,
+                vec![
+                    (None, Some(accounts.bob), 10_000),
+                    (Some(accounts.bob), None, 10_000),
+                    (Some(accounts.bob), Some(accounts.alice), 50),
+                ]
+            );
+            assert_eq!(contract.transfer_seq(), 4);
+            assert_eq!(contract.total_minted(), minted);
+            assert_eq!(contract.total_burned(), burned);
+            assert_eq!(contract.balance_of(accounts.bob), 50);
+            assert_eq!(contract.balance_of(accounts.alice), 950);
+            assert_eq!(contract.total_supply(), 1_000);
+            assert!(contract.check_supply_invariant());
+
+            assert_eq!(contract.open_flash_loan(accounts.bob, 10_000), Ok(50));
+            assert_eq!(
+                contract.settle_flash_loan(accounts.bob, 10_000, 51),
+                Err(PSP22Error::Custom(String::from("flash loan not repaid")))
+            );
+        }
+
+        #[ink::test]
+        fn recover_account_after_timelock() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 300, vec![]), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.approve(accounts.django, 120), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(contract.approve(accounts.django, 30), Ok(()));
+
+            set_caller(accounts.alice);
+            assert_eq!(
+                contract.recover_account(accounts.bob, accounts.charlie, vec![]),
+                Err(PSP22Error::Custom(String::from("no recovery scheduled")))
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(1_000);
+            assert_eq!(
+                contract.schedule_recovery(accounts.bob, accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.pending_recovery(accounts.bob),
+                Some((accounts.charlie, 1_000 + RECOVERY_DELAY_MS))
+            );
+            assert_eq!(
+                contract.recover_account(accounts.bob, accounts.charlie, vec![accounts.django]),
+                Err(PSP22Error::Custom(String::from("recovery timelocked")))
+            );
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                1_000 + RECOVERY_DELAY_MS,
+            );
+            let events_before = ink::env::test::recorded_events().count();
+            assert_eq!(
+                contract.recover_account(accounts.bob, accounts.charlie, vec![accounts.django]),
+                Ok(())
+            );
+            let approvals: Vec<_> = ink::env::test::recorded_events()
+                .skip(events_before)
+                .filter_map(|event| match decode_event(&event) {
+                    Event::Approval(Approval {
+                        owner,
+                        spender,
+                        value,
+                        ..
+                    }) => Some((owner, spender, value)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                approvals,
+                vec![
+                    (accounts.bob, accounts.django, 0),
+                    (accounts.charlie, accounts.django, 150),
+                ]
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.charlie), 300);
+            assert_eq!(contract.allowance(accounts.bob, accounts.django), 0);
+            assert_eq!(contract.allowance(accounts.charlie, accounts.django), 150);
+            assert_eq!(contract.last_approval(accounts.bob, accounts.django), None);
+            assert_eq!(contract.approval_count(accounts.bob), 0);
+            assert_eq!(contract.approval_count(accounts.charlie), 1);
+            assert_eq!(contract.pending_recovery(accounts.bob), None);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn kyc_gate_requires_verified_accounts() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_kyc_admin(accounts.django), Ok(()));
+            assert_eq!(
+                contract.set_kyc_verified(accounts.bob, true),
+                Err(PSP22Error::Custom(String::from("not kyc admin")))
+            );
+
+            // Switch off: nobody needs to be verified.
+            assert_eq!(contract.transfer(accounts.charlie, 100, vec![]), Ok(()));
+
+            set_caller(accounts.django);
+            assert_eq!(contract.set_kyc_verified(accounts.alice, true), Ok(()));
+            assert_eq!(contract.set_kyc_verified(accounts.bob, true), Ok(()));
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_kyc_required(true), Ok(()));
+
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            let kyc_required = Err(PSP22Error::Custom(String::from("kyc required")));
+            assert_eq!(contract.transfer(accounts.eve, 100, vec![]), kyc_required);
+            assert_eq!(contract.mint_to(accounts.eve, 100), kyc_required);
+
+            assert_eq!(contract.approve(accounts.charlie, 100), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 50, vec![]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.eve, 50, vec![]),
+                kyc_required
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 150);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn kyc_gate_covers_escrow_payouts_and_recovery() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_kyc_verified(accounts.alice, true), Ok(()));
+            assert_eq!(contract.set_kyc_verified(accounts.bob, true), Ok(()));
+            assert_eq!(contract.set_kyc_required(true), Ok(()));
+            let kyc_required = || PSP22Error::Custom(String::from("kyc required"));
+
+            assert_eq!(
+                contract.create_stream(accounts.eve, 1, 100),
+                Err(kyc_required())
+            );
+            let stream_id = contract.create_stream(accounts.bob, 1, 100).unwrap();
+            assert_eq!(contract.set_kyc_verified(accounts.bob, false), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            set_caller(accounts.bob);
+            assert_eq!(contract.withdraw_stream(stream_id), Err(kyc_required()));
+            set_caller(accounts.alice);
+            assert_eq!(contract.cancel_stream(stream_id), Err(kyc_required()));
+
+            assert_eq!(contract.mint_vesting(accounts.eve, 100, 0, 1_000), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(11_000);
+            set_caller(accounts.eve);
+            assert_eq!(contract.release(), Err(kyc_required()));
+
+            set_caller(accounts.alice);
+            assert_eq!(
+                contract.schedule_recovery(accounts.alice, accounts.eve),
+                Ok(())
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(
+                11_000 + RECOVERY_DELAY_MS,
+            );
+            assert_eq!(
+                contract.recover_account(accounts.alice, accounts.eve, vec![]),
+                Err(kyc_required())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn transfer_from_with_allowance_returns_remaining() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+            assert_eq!(contract.set_operator(accounts.charlie, true), Ok(()));
+
+            set_caller(accounts.bob);
+            let remaining =
+                contract.transfer_from_with_allowance(accounts.alice, accounts.django, 120, vec![]);
+            assert_eq!(remaining, Ok(180));
+            assert_eq!(
+                remaining,
+                Ok(contract.allowance(accounts.alice, accounts.bob))
+            );
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from_with_allowance(accounts.alice, accounts.django, 10, vec![]),
+                Ok(Balance::MAX)
+            );
+            assert_eq!(contract.balance_of(accounts.django), 130);
+        }
+
+        #[ink::test]
+        fn rebase_scales_balances_and_supply() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 300, vec![]), Ok(()));
+
+            assert_eq!(contract.rebase(3, 2), Ok(()));
+            assert_eq!(contract.rebase_factor(), (3, 2));
+            assert_eq!(contract.balance_of(accounts.alice), 1_050);
+            assert_eq!(contract.balance_of(accounts.bob), 450);
+            assert_eq!(contract.total_supply(), 1_500);
+            assert_eq!(contract.shares_of(accounts.bob), 300);
+
+            assert_eq!(contract.transfer(accounts.bob, 150, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+
+            assert_eq!(contract.rebase(1, 3), Ok(()));
+            assert_eq!(contract.rebase_factor(), (1, 2));
+            assert_eq!(contract.balance_of(accounts.alice), 300);
+            assert_eq!(contract.balance_of(accounts.bob), 200);
+            assert_eq!(contract.total_supply(), 500);
+            assert!(contract.check_supply_invariant());
+
+            assert_eq!(
+                contract.rebase(0, 1),
+                Err(PSP22Error::Custom(String::from("invalid factor")))
+            );
+        }
+
+        #[ink::test]
+        fn rebase_moves_whole_shares_and_reports_them() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 3, vec![]), Ok(()));
+            assert_eq!(contract.rebase(3, 2), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 4);
+            assert_eq!(contract.holder_count(), 2);
+            let last_transfer = || {
+                token_events()
+                    .filter_map(|event| match decode_event(&event) {
+                        Event::Transfer(transfer) => Some(transfer),
+                        _ => None,
+                    })
+                    .last()
+                    .unwrap()
+            };
+
+            // The whole balance moves all 3 shares.
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.charlie, 4, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 0);
+            assert_eq!(contract.balance_of(accounts.charlie), 4);
+            assert_eq!(last_transfer().value, 4);
+            assert_eq!(contract.holder_count(), 2);
+
+            // One token is worth less than a share; two round down to one share.
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer(accounts.django, 1, vec![]),
+                Err(PSP22Error::Custom(String::from("amount below one share")))
+            );
+            assert_eq!(contract.transfer(accounts.django, 2, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.charlie), 3);
+            assert_eq!(contract.balance_of(accounts.django), 1);
+            assert_eq!(last_transfer().value, 1);
+            assert_eq!(contract.holder_count(), 3);
+
+            let minted = contract.total_minted();
+            assert_eq!(
+                contract.mint_to(accounts.eve, 1),
+                Err(PSP22Error::Custom(String::from("amount below one share")))
+            );
+            assert_eq!(contract.mint_to(accounts.eve, 2), Ok(()));
+            assert_eq!(contract.balance_of(accounts.eve), 1);
+            assert_eq!(last_transfer().value, 1);
+            assert_eq!(contract.total_minted(), minted + 1);
+            assert_eq!(contract.holder_count(), 4);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn role_members_tracks_grants_and_revocations() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.grant_role(7, accounts.bob), Ok(()));
+            assert_eq!(contract.grant_role(7, accounts.charlie), Ok(()));
+            assert_eq!(contract.grant_role(7, accounts.django), Ok(()));
+            assert_eq!(contract.grant_role(7, accounts.bob), Ok(()));
+            assert_eq!(
+                contract.role_members(7),
+                vec![accounts.bob, accounts.charlie, accounts.django]
+            );
+
+            assert_eq!(contract.revoke_role(7, accounts.charlie), Ok(()));
+            assert_eq!(
+                contract.role_members(7),
+                vec![accounts.bob, accounts.django]
+            );
+            assert!(contract.has_role(7, accounts.bob));
+            assert!(!contract.has_role(7, accounts.charlie));
+            assert_eq!(contract.role_members(8), vec![]);
+        }
+
+        #[ink::test]
+        fn require_whole_units_rejects_fractions() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 150_000, vec![]), Ok(()));
+
+            assert_eq!(contract.set_require_whole_units(true), Ok(()));
+            assert_eq!(contract.transfer(accounts.bob, 200_000, vec![]), Ok(()));
+            let whole_units_only = Err(PSP22Error::Custom(String::from("whole units only")));
+            assert_eq!(
+                contract.transfer(accounts.bob, 150_000, vec![]),
+                whole_units_only
+            );
+
+            assert_eq!(contract.approve(accounts.charlie, 500_000), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 1, vec![]),
+                whole_units_only
+            );
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 100_000, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 450_000);
+        }
+
+        #[ink::test]
+        fn account_info_matches_individual_getters() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 300, vec![]), Ok(()));
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.alice), Ok(()));
+            assert_eq!(contract.freeze_batch(vec![accounts.bob]), Ok(()));
+            assert_eq!(contract.set_transfer_allowed(accounts.alice, true), Ok(()));
+            assert_eq!(contract.set_lockup_end(5_000), Ok(()));
+
+            let bob = contract.account_info(accounts.bob);
+            assert_eq!(bob.balance, contract.balance_of(accounts.bob));
+            assert_eq!(bob.frozen, contract.is_frozen(accounts.bob));
+            assert_eq!(bob.locked_until, contract.lockup_end());
+            assert_eq!((bob.staked, bob.claimable_income), (0, 0));
+
+            let alice = contract.account_info(accounts.alice);
+            assert_eq!(alice.balance, 700);
+            assert!(!alice.frozen);
+            assert_eq!(alice.locked_until, 0);
+        }
+
+        #[ink::test]
+        fn transfer_events_carry_consecutive_seq() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_zero_address_burns(true), Ok(()));
+
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            assert_eq!(contract.mint_to(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 100), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 100, vec![]),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer(AccountId::from([0u8; 32]), 30, vec![]),
+                Ok(())
+            );
+
+            let seqs: Vec<u64> = ink::env::test::recorded_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::Transfer(Transfer { seq, .. }) => Some(seq),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(seqs, vec![1, 2, 3, 4]);
+            assert_eq!(contract.transfer_seq(), 4);
+        }
+
+        #[ink::test]
+        fn reapprove_requires_zero_step_when_enabled() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.approve(accounts.bob, 200), Ok(()));
+
+            assert_eq!(contract.set_require_zero_before_reapprove(true), Ok(()));
+            assert_eq!(
+                contract.approve(accounts.bob, 300),
+                Err(PSP22Error::Custom(String::from("allowance not zero")))
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 200);
+
+            assert_eq!(contract.approve(accounts.bob, 0), Ok(()));
+            assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn bridge_role_mints_and_burns() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.grant_role(BRIDGE_ROLE, accounts.eve), Ok(()));
+
+            let not_bridge = Err(PSP22Error::Custom(String::from("not bridge")));
+            assert_eq!(
+                contract.bridge_mint(accounts.bob, 100, 2, [1u8; 32]),
+                not_bridge
+            );
+            assert_eq!(contract.bridge_burn(accounts.alice, 100, 2), not_bridge);
+
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.bridge_mint(accounts.bob, 100, 2, [1u8; 32]),
+                Ok(())
+            );
+            assert_eq!(
+                contract.bridge_mint(accounts.bob, 100, 2, [1u8; 32]),
+                Err(PSP22Error::Custom(String::from("already processed")))
+            );
+            assert_eq!(
+                contract.bridge_mint(AccountId::from([0u8; 32]), 100, 2, [2u8; 32]),
+                Err(PSP22Error::ZeroRecipientAddress)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 100);
+            assert_eq!(contract.total_supply(), 1_100);
+
+            assert_eq!(contract.bridge_burn(accounts.bob, 40, 3), Ok(()));
+            assert_eq!(
+                contract.bridge_burn(AccountId::from([0u8; 32]), 1, 3),
+                Err(PSP22Error::ZeroSenderAddress)
+            );
+            assert_eq!(contract.balance_of(accounts.bob), 60);
+            assert_eq!(contract.total_supply(), 1_060);
+
+            let bridge_events = token_events()
+                .filter(|event| {
+                    matches!(
+                        decode_event(event),
+                        Event::BridgeMint(_) | Event::BridgeBurn(_)
+                    )
+                })
+                .count();
+            assert_eq!(bridge_events, 2);
+        }
+
+        #[ink::test]
+        fn decrease_allowance_floors_at_zero_when_saturating() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+
+            assert_eq!(
+                contract.decrease_allowance(accounts.bob, 150),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 100);
+
+            assert_eq!(contract.set_saturating_decrease(true), Ok(()));
+            assert_eq!(contract.decrease_allowance(accounts.bob, 150), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn revoking_nonzero_allowance_emits_revocation() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            let revocations = || {
+                token_events()
+                    .filter(|event| matches!(decode_event(event), Event::Revocation(_)))
+                    .count()
+            };
+
+            assert_eq!(contract.approve(accounts.bob, 0), Ok(()));
+            assert_eq!(revocations(), 0);
+
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.revoke_approval(accounts.bob), Ok(()));
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(revocations(), 1);
+
+            let events: Vec<_> = token_events().collect();
+            match decode_event(&events[events.len() - 2]) {
+                Event::Approval(Approval { value, .. }) => assert_eq!(value, 0),
+                _ => panic!("expected Approval"),
+            }
+
+            assert_eq!(contract.approve(accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 0), Ok(()));
+            assert_eq!(revocations(), 2);
+        }
+
+        #[ink::test]
+        fn freeze_all_blocks_transfers_with_reason() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            let stream_id = contract.create_stream(accounts.charlie, 1, 100).unwrap();
+            assert_eq!(contract.freeze_reason(), None);
+
+            assert_eq!(contract.freeze_all(String::from("court order 42")), Ok(()));
+            assert_eq!(
+                contract.freeze_reason(),
+                Some(String::from("court order 42"))
+            );
+            let frozen = Err(PSP22Error::Custom(String::from("all transfers frozen")));
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), frozen);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 100, vec![]),
+                frozen
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.burn(10), frozen);
+            assert_eq!(contract.cancel_stream(stream_id), frozen);
+            assert_eq!(contract.balance_of(accounts.alice), 900);
+            assert_eq!(contract.unfreeze_all(), Ok(()));
+            assert_eq!(contract.freeze_reason(), None);
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+        }
+
+        /// Storage reads and writes of a plain `transfer`, `approve` and
+        /// `transfer_from`, as `(reads, writes)`. Raise these only with a reason:
+        /// every access adds on-chain weight to the most common calls. `approve`
+        /// reads the previous allowance and updates `approval_count`.
+        ///
+        /// Off-chain, the messages are called directly on the struct, so only
+        /// `Mapping` accesses are counted. The root storage cell with every plain
+        /// field, loaded and written back once per call on-chain, is not measured,
+        /// and growing it does not show up here.
+        const TRANSFER_STORAGE_BASELINE: (usize, usize) = (5, 3);
+        const APPROVE_STORAGE_BASELINE: (usize, usize) = (2, 3);
+        const TRANSFER_FROM_STORAGE_BASELINE: (usize, usize) = (7, 4);
+
+        #[ink::test]
+        fn storage_access_stays_within_baseline() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            let callee = AccountId::from(CONTRACT);
+            let storage_rw =
+                || ink::env::test::get_contract_storage_rw::<ink::env::DefaultEnvironment>(&callee);
+
+            let before = storage_rw();
+            assert_eq!(contract.transfer(accounts.bob, 100, vec![]), Ok(()));
+            let after = storage_rw();
+            let transfer = (after.0 - before.0, after.1 - before.1);
+
+            let before = storage_rw();
+            assert_eq!(contract.approve(accounts.charlie, 500), Ok(()));
+            let after = storage_rw();
+            let approve = (after.0 - before.0, after.1 - before.1);
+
+            set_caller(accounts.charlie);
+            let before = storage_rw();
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 100, vec![]),
+                Ok(())
+            );
+            let after = storage_rw();
+            let transfer_from = (after.0 - before.0, after.1 - before.1);
+
+            let within = |actual: (usize, usize), baseline: (usize, usize)| {
+                actual.0 <= baseline.0 && actual.1 <= baseline.1
+            };
+            assert!(
+                within(transfer, TRANSFER_STORAGE_BASELINE),
+                "transfer: {transfer:?}"
+            );
+            assert!(
+                within(approve, APPROVE_STORAGE_BASELINE),
+                "approve: {approve:?}"
+            );
+            assert!(
+                within(transfer_from, TRANSFER_FROM_STORAGE_BASELINE),
+                "transfer_from: {transfer_from:?}"
+            );
+        }
+
+        #[ink::test]
+        fn mint_vesting_releases_after_cliff() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+
+            assert_eq!(
+                contract.mint_vesting(accounts.bob, 1_200, 3_000, 12_000),
+                Ok(())
+            );
+            assert_eq!(
+                contract.mint_vesting(accounts.bob, 100, 0, 1_000),
+                Err(PSP22Error::Custom(String::from("schedule exists")))
+            );
+            assert_eq!(contract.total_supply(), 2_200);
+            assert_eq!(contract.balance_of(AccountId::from(CONTRACT)), 1_200);
+
+            set_caller(accounts.bob);
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(12_000);
+            assert_eq!(contract.releasable(accounts.bob), 0);
+            assert_eq!(contract.release(), Ok(0));
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(16_000);
+            assert_eq!(contract.release(), Ok(600));
+            assert_eq!(contract.balance_of(accounts.bob), 600);
+
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(30_000);
+            assert_eq!(contract.release(), Ok(600));
+            assert_eq!(contract.balance_of(accounts.bob), 1_200);
+            assert_eq!(contract.vesting_schedule(accounts.bob), None);
+            assert_eq!(
+                contract.release(),
+                Err(PSP22Error::Custom(String::from("no schedule")))
+            );
+        }
+
+        #[ink::test]
+        fn vesting_escrow_survives_rebase_and_kyc() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            for account in [accounts.alice, accounts.bob, accounts.charlie] {
+                assert_eq!(contract.set_kyc_verified(account, true), Ok(()));
+            }
+            assert_eq!(contract.set_kyc_required(true), Ok(()));
+
+            // The contract's own escrow account needs no verification.
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(10_000);
+            assert_eq!(contract.mint_vesting(accounts.bob, 1_000, 0, 1_000), Ok(()));
+            assert!(contract.create_stream(accounts.charlie, 1, 100).is_ok());
+            assert_eq!(contract.balance_of(AccountId::from(CONTRACT)), 1_100);
+
+            // Halving every balance halves what the schedule owes with it, so the
+            // release neither reverts nor dips into the stream deposit.
+            assert_eq!(contract.rebase(1, 2), Ok(()));
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(11_000);
+            assert_eq!(contract.releasable(accounts.bob), 500);
+            set_caller(accounts.bob);
+            assert_eq!(contract.release(), Ok(500));
+            assert_eq!(contract.balance_of(accounts.bob), 500);
+            assert_eq!(contract.balance_of(AccountId::from(CONTRACT)), 50);
+            assert_eq!(contract.vesting_schedule(accounts.bob), None);
+            assert!(contract.check_supply_invariant());
+        }
+
+        #[ink::test]
+        fn verify_post_upgrade_checks_supply_state() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 300, vec![]), Ok(()));
+
+            assert_eq!(contract.verify_post_upgrade(), Ok(()));
+            assert!(token_events()
+                .any(|event| matches!(decode_event(&event), Event::UpgradeVerified(_))));
+
+            contract.holder_count = 0;
+            assert_eq!(
+                contract.verify_post_upgrade(),
+                Err(PSP22Error::Custom(String::from("implausible holder count")))
+            );
+
+            contract.holder_count = 2;
+            let shares = contract.to_shares(5);
+            contract.write_shares(accounts.charlie, 0, shares);
+            assert_eq!(
+                contract.verify_post_upgrade(),
+                Err(PSP22Error::Custom(String::from("supply mismatch")))
+            );
+        }
+
+        #[ink::test]
+        fn scale_to_converts_between_decimals() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.scale_to(123_456, 18), Ok(123_456 * 10u128.pow(13)));
+            assert_eq!(
+                contract.scale_from(123_456 * 10u128.pow(13), 18),
+                Ok(123_456)
+            );
+            assert_eq!(contract.scale_from(10u128.pow(13) - 1, 18), Ok(0));
+            assert_eq!(contract.scale_to(123_456, 2), Ok(123));
+            assert_eq!(contract.scale_to(7, 5), Ok(7));
+
+            let overflow = Err(PSP22Error::Custom(String::from("overflow")));
+            assert_eq!(contract.scale_to(Balance::MAX / 10, 18), overflow);
+            assert_eq!(contract.scale_to(1, 255), overflow);
+            assert_eq!(contract.scale_from(Balance::MAX, 255), Ok(0));
+        }
+
+        #[ink::test]
+        fn approvals_are_capped_per_owner() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_max_approvals_per_owner(2), Ok(()));
+
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.approve(accounts.charlie, 100), Ok(()));
+            assert_eq!(contract.approval_count(accounts.alice), 2);
+            // Changing an existing nonzero allowance takes no new slot.
+            assert_eq!(contract.approve(accounts.bob, 200), Ok(()));
+            assert_eq!(
+                contract.approve(accounts.django, 100),
+                Err(PSP22Error::Custom(String::from("too many approvals")))
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.django), 0);
+
+            assert_eq!(contract.revoke_approval(accounts.bob), Ok(()));
+            assert_eq!(contract.approval_count(accounts.alice), 1);
+            assert_eq!(contract.approve(accounts.django, 100), Ok(()));
+            assert_eq!(contract.approval_count(accounts.alice), 2);
+
+            // Spending an allowance down to zero frees its slot too.
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.charlie, 100, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.approval_count(accounts.alice), 1);
+        }
+
+        #[ink::test]
+        fn guardian_can_pause_but_only_admin_unpauses() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.set_guardian(accounts.charlie), Ok(()));
+            assert_eq!(contract.guardian(), accounts.charlie);
+
+            set_caller(accounts.charlie);
+            assert_eq!(contract.pause(), Ok(()));
+            assert!(contract.paused());
+            assert_eq!(
+                contract.unpause(),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            assert_eq!(
+                contract.set_guardian(accounts.charlie),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.unpause(), Ok(()));
+            assert!(!contract.paused());
+        }
+
+        #[ink::test]
+        fn admin_handover_moves_default_guardian() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.guardian(), accounts.alice);
+            assert_eq!(contract.propose_admin(accounts.bob), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_admin(), Ok(()));
+            assert_eq!(contract.guardian(), accounts.bob);
+
+            set_caller(accounts.alice);
+            assert_eq!(
+                contract.pause(),
+                Err(PSP22Error::Custom(String::from("not admin")))
+            );
+            assert!(!contract.paused());
+
+            // A guardian set explicitly survives the next handover.
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_guardian(accounts.charlie), Ok(()));
+            assert_eq!(contract.propose_admin(accounts.django), Ok(()));
+            set_caller(accounts.django);
+            assert_eq!(contract.accept_admin(), Ok(()));
+            assert_eq!(contract.guardian(), accounts.charlie);
+        }
+
+        #[ink::test]
+        fn transfer_from_falls_back_to_operator_approval() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 100, vec![]),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_operator(accounts.bob, true), Ok(()));
+            let events_before = ink::env::test::recorded_events().count();
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.django, 100, vec![]),
+                Ok(())
+            );
+            assert_eq!(contract.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(contract.last_approval(accounts.alice, accounts.bob), None);
+            assert_eq!(contract.balance_of(accounts.django), 100);
+            // Only the `Transfer` event; operator transfers emit no `Approval`.
+            assert_eq!(ink::env::test::recorded_events().count(), events_before + 1);
+        }
+
+        #[ink::test]
+        fn burn_with_reason_accepts_only_known_codes() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            for reason in [
+                BurnReason::Generic,
+                BurnReason::Redemption,
+                BurnReason::Correction,
+                BurnReason::Penalty,
+            ] {
+                assert_eq!(contract.burn_with_reason(10, reason as u8), Ok(()));
+                let event = ink::env::test::recorded_events().last().unwrap();
+                match decode_event(&event) {
+                    Event::Burned(Burned {
+                        from,
+                        value,
+                        reason_code,
+                    }) => {
+                        assert_eq!(from, accounts.alice);
+                        assert_eq!(value, 10);
+                        assert_eq!(reason_code, reason as u8);
+                    }
+                    _ => panic!("expected Burned event"),
+                }
+            }
+            assert_eq!(contract.burn(10), Ok(()));
+            assert_eq!(contract.balance_of(accounts.alice), 950);
+            assert_eq!(contract.total_supply(), 950);
+
+            assert_eq!(
+                contract.burn_with_reason(10, 4),
+                Err(PSP22Error::Custom(String::from("unknown burn reason")))
+            );
+            assert_eq!(contract.total_supply(), 950);
+        }
+
+        #[ink::test]
+        fn admin_balance_changes_emit_balance_events() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 300, vec![]), Ok(()));
+
+            assert_eq!(contract.rebase(2, 1), Ok(()));
+            match decode_event(&token_events().last().unwrap()) {
+                Event::BalanceAdjusted(BalanceAdjusted { account, old, new }) => {
+                    assert_eq!(account, None);
+                    assert_eq!(old, 1_000);
+                    assert_eq!(new, 2_000);
+                }
+                _ => panic!("expected BalanceAdjusted event"),
+            }
+
+            assert_eq!(
+                contract.schedule_recovery(accounts.bob, accounts.charlie),
+                Ok(())
+            );
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(RECOVERY_DELAY_MS);
+            assert_eq!(
+                contract.recover_account(accounts.bob, accounts.charlie, vec![]),
+                Ok(())
+            );
+            let transfer = token_events()
+                .map(|event| decode_event(&event))
+                .filter_map(|event| match event {
+                    Event::Transfer(transfer) => Some(transfer),
+                    _ => None,
+                })
+                .last()
+                .unwrap();
+            assert_eq!(transfer.from, Some(accounts.bob));
+            assert_eq!(transfer.to, Some(accounts.charlie));
+            assert_eq!(transfer.value, 600);
+        }
+
+        #[ink::test]
+        fn admin_is_exempt_from_lockup() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 1_000, vec![]), Ok(()));
+            assert_eq!(contract.set_lockup_end(10_000), Ok(()));
+            assert!(contract.is_transfer_allowed(accounts.alice));
+            assert_eq!(contract.account_info(accounts.alice).locked_until, 0);
+
+            assert_eq!(contract.transfer(accounts.django, 500, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.django), 500);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.django, 10, vec![]),
+                Err(PSP22Error::Custom(String::from("locked up")))
+            );
+        }
+
+        #[ink::test]
+        fn quote_transfer_matches_fee_branching() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.transfer(accounts.bob, 2_000, vec![]), Ok(()));
+            assert_eq!(contract.set_transfer_fee_bps(100), Ok(()));
+            assert_eq!(contract.set_native_fee_amount(7), Ok(()));
+            let quote = |contract: &RedToken, net, fee, debit, exempt| {
+                assert_eq!(
+                    contract.quote_transfer(accounts.charlie, 500),
+                    Ok(TransferQuote {
+                        net_to_recipient: net,
+                        red_fee: fee,
+                        total_debit: debit,
+                        native_fee: 7,
+                        exempt,
+                    })
+                );
+            };
+
+            for (mode, net, debit) in [
+                (FeeMode::Inclusive, 495, 500),
+                (FeeMode::Exclusive, 500, 505),
+            ] {
+                set_caller(accounts.alice);
+                assert_eq!(contract.set_fee_mode(mode), Ok(()));
+                quote(&contract, 500, 0, 500, true);
+
+                set_caller(accounts.bob);
+                quote(&contract, net, 5, debit, false);
+                let before = contract.balance_of(accounts.bob);
+                assert_eq!(contract.transfer(accounts.charlie, 500, vec![]), Ok(()));
+                assert_eq!(before - contract.balance_of(accounts.bob), debit);
+            }
+        }
+
+        #[ink::test]
+        fn allowance_changes_carry_old_and_new_values() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.approve(accounts.bob, 100), Ok(()));
+            assert_eq!(contract.increase_allowance(accounts.bob, 50), Ok(()));
+            assert_eq!(contract.decrease_allowance(accounts.bob, 30), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_from(accounts.alice, accounts.bob, 20, vec![]),
+                Ok(())
+            );
+
+            let changes: Vec<_> = token_events()
+                .filter_map(|event| match decode_event(&event) {
+                    Event::AllowanceChanged(AllowanceChanged {
+                        owner,
+                        spender,
+                        old,
+                        new,
+                    }) => {
+                        assert_eq!((owner, spender), (accounts.alice, accounts.bob));
+                        Some((old, new))
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(changes, vec![(0, 100), (100, 150), (150, 120), (120, 100)]);
+        }
+
+        #[ink::test]
+        fn batches_accept_max_batch_size_entries() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(1_000, accounts.alice, 5u8);
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.alice), Ok(()));
+            assert_eq!(contract.max_batch_size(), MAX_BATCH_SIZE as u32);
+            let too_large = Err(PSP22Error::Custom(String::from("batch too large")));
+
+            assert_eq!(
+                contract.increase_allowance_batch(
+                    vec![accounts.bob; MAX_BATCH_SIZE],
+                    vec![1; MAX_BATCH_SIZE]
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                contract.allowance(accounts.alice, accounts.bob),
+                MAX_BATCH_SIZE as Balance
+            );
+            assert_eq!(
+                contract.increase_allowance_batch(
+                    vec![accounts.bob; MAX_BATCH_SIZE + 1],
+                    vec![1; MAX_BATCH_SIZE + 1]
+                ),
+                too_large
+            );
+
+            assert_eq!(
+                contract.freeze_batch(vec![accounts.charlie; MAX_BATCH_SIZE]),
+                Ok(())
+            );
+            assert!(contract.is_frozen(accounts.charlie));
+            assert_eq!(
+                contract.unfreeze_batch(vec![accounts.charlie; MAX_BATCH_SIZE + 1]),
+                too_large
+            );
+            assert!(contract.is_frozen(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn freeze_batch_blocks_and_unfreeze_batch_restores_transfers() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            for account in [accounts.bob, accounts.charlie, accounts.django] {
+                assert_eq!(contract.transfer(account, 1_000, vec![]), Ok(()));
+            }
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.eve), Ok(()));
+            let before = token_events().count();
+
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.freeze_batch(vec![accounts.bob, accounts.charlie]),
+                Ok(())
+            );
+            assert!(contract.is_frozen(accounts.bob));
+            assert!(contract.is_frozen(accounts.charlie));
+            assert_eq!(token_events().count(), before + 2);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer(accounts.django, 10, vec![]),
+                Err(PSP22Error::Custom(String::from("account frozen")))
+            );
+            set_caller(accounts.django);
+            assert_eq!(
+                contract.transfer(accounts.charlie, 10, vec![]),
+                Err(PSP22Error::Custom(String::from("account frozen")))
+            );
+
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.unfreeze_batch(vec![accounts.bob, accounts.charlie]),
+                Ok(())
+            );
+            assert!(!contract.is_frozen(accounts.bob));
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.transfer(accounts.django, 10, vec![]), Ok(()));
+            assert_eq!(contract.balance_of(accounts.bob), 990);
+        }
+
+        #[ink::test]
+        fn freeze_batch_rejects_non_freezers_and_oversized_batches() {
+            let accounts = accounts_with_contract_callee();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.bob), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.freeze_batch(vec![accounts.alice; MAX_BATCH_SIZE + 1]),
+                Err(PSP22Error::Custom(String::from("batch too large")))
+            );
+
+            let not_freezer = Err(PSP22Error::Custom(String::from("not freezer")));
+            for caller in [accounts.alice, accounts.charlie] {
+                set_caller(caller);
+                assert_eq!(contract.freeze_batch(vec![accounts.charlie]), not_freezer);
+                assert_eq!(contract.unfreeze_batch(vec![accounts.charlie]), not_freezer);
+            }
+            assert!(!contract.is_frozen(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn roles_are_granted_and_revoked_by_the_admin() {
+            let accounts = default_accounts();
+            set_caller(accounts.alice);
+            let mut contract = RedToken::new(100_000, accounts.alice, 5u8);
+
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.bob), Ok(()));
+            assert!(contract.has_role(FREEZER_ROLE, accounts.bob));
+            assert!(!contract.has_role(FREEZER_ROLE, accounts.charlie));
+            assert_eq!(contract.revoke_role(FREEZER_ROLE, accounts.bob), Ok(()));
+            assert!(!contract.has_role(FREEZER_ROLE, accounts.bob));
+
+            set_caller(accounts.bob);
+            let not_admin = Err(PSP22Error::Custom(String::from("not admin")));
+            assert_eq!(contract.grant_role(FREEZER_ROLE, accounts.bob), not_admin);
+            assert_eq!(
+                contract.revoke_role(FREEZER_ROLE, accounts.alice),
+                not_admin
+            );
+            assert!(!contract.has_role(FREEZER_ROLE, accounts.bob));
+        }
+
+        /// Replays each PSP22 operation and asserts the exact events it emits, in order.
+        ///
+        /// This is the reference for integrators indexing RED events.
+        mod events {
+            use super::*;
+
+            fn recorded() -> Vec<Event> {
+                ink::env::test::recorded_events()
+                    .map(|event| decode_event(&event))
+                    .collect()
+            }
+
+            fn assert_transfer(
+                event: &Event,
+                from: Option<AccountId>,
+                to: Option<AccountId>,
+                value: Balance,
+                data: &[u8],
+            ) {
+                match event {
+                    Event::Transfer(transfer) => {
+                        assert_eq!(transfer.from, from, "Transfer.from");
+                        assert_eq!(transfer.to, to, "Transfer.to");
+                        assert_eq!(transfer.value, value, "Transfer.value");
+                        assert_eq!(transfer.data, data, "Transfer.data");
+                    }
+                    _ => panic!("expected Transfer event"),
+                }
+            }
+
+            fn assert_approval(
+                event: &Event,
+                owner: AccountId,
+                spender: AccountId,
+                value: Balance,
+                data: &[u8],
+            ) {
+                match event {
+                    Event::Approval(approval) => {
+                        assert_eq!(approval.owner, owner, "Approval.owner");
+                        assert_eq!(approval.spender, spender, "Approval.spender");
+                        assert_eq!(approval.value, value, "Approval.value");
+                        assert_eq!(approval.data, data, "Approval.data");
+                    }
+                    _ => panic!("expected Approval event"),
+                }
+            }
+
+            fn assert_allowance_changed(
+                event: &Event,
+                owner: AccountId,
+                spender: AccountId,
+                old: Balance,
+                new: Balance,
+            ) {
+                match event {
+                    Event::AllowanceChanged(changed) => {
+                        assert_eq!(changed.owner, owner, "AllowanceChanged.owner");
+                        assert_eq!(changed.spender, spender, "AllowanceChanged.spender");
+                        assert_eq!(changed.old, old, "AllowanceChanged.old");
+                        assert_eq!(changed.new, new, "AllowanceChanged.new");
+                    }
+                    _ => panic!("expected AllowanceChanged event"),
+                }
+            }
+
+            fn setup() -> (
+                ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment>,
+                RedToken,
+            ) {
+                let accounts = accounts_with_contract_callee();
+                set_caller(accounts.alice);
+                let contract = RedToken::new(1_000, accounts.alice, 5u8);
+                (accounts, contract)
+            }
+
+            #[ink::test]
+            fn transfer_emits_single_transfer() {
+                let (accounts, mut contract) = setup();
+
+                assert_eq!(contract.transfer(accounts.bob, 100, vec![1, 2, 3]), Ok(()));
+
+                let events = recorded();
+                assert_eq!(events.len(), 1);
+                assert_transfer(
+                    &events[0],
+                    Some(accounts.alice),
+                    Some(accounts.bob),
+                    100,
+                    &[1, 2, 3],
+                );
+            }
+
+            #[ink::test]
+            fn transfer_from_emits_transfer_then_remaining_approval() {
+                let (accounts, mut contract) = setup();
+                assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+
+                set_caller(accounts.bob);
+                assert_eq!(
+                    contract.transfer_from(accounts.alice, accounts.charlie, 100, vec![7]),
+                    Ok(())
+                );
+                assert_eq!(contract.allowance(accounts.alice, accounts.bob), 200);
+
+                let events = recorded();
+                assert_eq!(events.len(), 5);
+                assert_allowance_changed(&events[0], accounts.alice, accounts.bob, 0, 300);
+                assert_approval(&events[1], accounts.alice, accounts.bob, 300, &[]);
+                assert_transfer(
+                    &events[2],
+                    Some(accounts.alice),
+                    Some(accounts.charlie),
+                    100,
+                    &[7],
+                );
+                assert_allowance_changed(&events[3], accounts.alice, accounts.bob, 300, 200);
+                assert_approval(&events[4], accounts.alice, accounts.bob, 200, &[7]);
+            }
+
+            #[ink::test]
+            fn approve_emits_approval_with_new_allowance() {
+                let (accounts, mut contract) = setup();
+
+                assert_eq!(contract.approve(accounts.bob, 300), Ok(()));
+                assert_eq!(contract.approve(accounts.bob, 50), Ok(()));
+
+                let events = recorded();
+                assert_eq!(events.len(), 4);
+                assert_allowance_changed(&events[0], accounts.alice, accounts.bob, 0, 300);
+                assert_approval(&events[1], accounts.alice, accounts.bob, 300, &[]);
+                assert_allowance_changed(&events[2], accounts.alice, accounts.bob, 300, 50);
+                assert_approval(&events[3], accounts.alice, accounts.bob, 50, &[]);
+            }
+
+            #[ink::test]
+            fn allowance_changes_emit_resulting_allowance() {
+                let (accounts, mut contract) = setup();
+
+                assert_eq!(contract.increase_allowance(accounts.bob, 100), Ok(()));
+                assert_eq!(contract.increase_allowance(accounts.bob, 50), Ok(()));
+                assert_eq!(contract.decrease_allowance(accounts.bob, 30), Ok(()));
+
+                let events = recorded();
+                assert_eq!(events.len(), 6);
+                assert_allowance_changed(&events[0], accounts.alice, accounts.bob, 0, 100);
+                assert_approval(&events[1], accounts.alice, accounts.bob, 100, &[]);
+                assert_allowance_changed(&events[2], accounts.alice, accounts.bob, 100, 150);
+                assert_approval(&events[3], accounts.alice, accounts.bob, 150, &[]);
+                assert_allowance_changed(&events[4], accounts.alice, accounts.bob, 150, 120);
+                assert_approval(&events[5], accounts.alice, accounts.bob, 120, &[]);
+            }
+
+            #[ink::test]
+            fn failed_operations_emit_nothing() {
+                let (accounts, mut contract) = setup();
+
+                assert_eq!(
+                    contract.transfer(accounts.bob, 5_000, vec![]),
+                    Err(PSP22Error::InsufficientBalance)
+                );
+                assert_eq!(
+                    contract.decrease_allowance(accounts.bob, 1),
+                    Err(PSP22Error::InsufficientAllowance)
+                );
+
+                assert!(recorded().is_empty());
+            }
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use ink_e2e::build_message;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn metadata_is_callable_through_contract_ref(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let contract_account_id = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            let token_name = build_message::<RedTokenRef>(contract_account_id.clone())
+                .call(|token| token.token_name());
+            let token_symbol = build_message::<RedTokenRef>(contract_account_id.clone())
+                .call(|token| token.token_symbol());
+            let token_decimals = build_message::<RedTokenRef>(contract_account_id.clone())
+                .call(|token| token.token_decimals());
+
+            let name = client
+                .call_dry_run(&ink_e2e::alice(), &token_name, 0, None)
+                .await
+                .return_value();
+            let symbol = client
+                .call_dry_run(&ink_e2e::alice(), &token_symbol, 0, None)
+                .await
+                .return_value();
+            let decimals = client
+                .call_dry_run(&ink_e2e::alice(), &token_decimals, 0, None)
+                .await
+                .return_value();
+
+            assert_eq!(name, Some(String::from("Real Estate DAO")));
+            assert_eq!(symbol, Some(String::from("RED")));
+            assert_eq!(decimals, 5);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn instantiate_child_deploys_independent_token(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let code_hash = client
+                .upload("red", &ink_e2e::alice(), None)
+                .await
+                .expect("upload failed")
+                .code_hash;
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let parent = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            let instantiate_child = build_message::<RedTokenRef>(parent.clone())
+                .call(|token| token.instantiate_child(code_hash, 5_000, 2u8, [7u8; 32]));
+            let child = client
+                .call(&ink_e2e::alice(), instantiate_child, 0, None)
+                .await
+                .expect("instantiate_child failed")
+                .return_value()
+                .expect("instantiate_child reverted");
+
+            let children =
+                build_message::<RedTokenRef>(parent.clone()).call(|token| token.children());
+            let children = client
+                .call_dry_run(&ink_e2e::alice(), &children, 0, None)
+                .await
+                .return_value();
+            assert_eq!(children, vec![child]);
+
+            let transfer = build_message::<RedTokenRef>(child.clone())
+                .call(|token| token.transfer(bob, 1_000, vec![]));
+            client
+                .call(&ink_e2e::alice(), transfer, 0, None)
+                .await
+                .expect("child transfer failed");
+
+            let child_balance =
+                build_message::<RedTokenRef>(child.clone()).call(|token| token.balance_of(bob));
+            let parent_balance =
+                build_message::<RedTokenRef>(parent.clone()).call(|token| token.balance_of(bob));
+            let child_balance = client
+                .call_dry_run(&ink_e2e::alice(), &child_balance, 0, None)
+                .await
+                .return_value();
+            let parent_balance = client
+                .call_dry_run(&ink_e2e::alice(), &parent_balance, 0, None)
+                .await
+                .return_value();
+            assert_eq!(child_balance, 1_000);
+            assert_eq!(parent_balance, 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "receiver_mock/Cargo.toml")]
+        async fn transfer_from_asks_contract_recipients(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            use receiver_mock::ReceiverMockRef;
+
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let token = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let accepting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(true),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate accepting receiver failed")
+                .account_id;
+            let rejecting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(false),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate rejecting receiver failed")
+                .account_id;
+
+            for receiver in [accepting, rejecting] {
+                let allow = build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.set_recipient_contract_allowed(receiver, true));
+                client
+                    .call(&ink_e2e::alice(), allow, 0, None)
+                    .await
+                    .expect("set_recipient_contract_allowed failed");
+            }
+
+            let approve =
+                build_message::<RedTokenRef>(token.clone()).call(|token| token.approve(bob, 200));
+            client
+                .call(&ink_e2e::alice(), approve, 0, None)
+                .await
+                .expect("approve failed");
+
+            let to_accepting = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.transfer_from(admin, accepting, 100, vec![]));
+            client
+                .call(&ink_e2e::bob(), to_accepting, 0, None)
+                .await
+                .expect("transfer_from to accepting receiver failed");
+
+            let last_received = build_message::<ReceiverMockRef>(accepting.clone())
+                .call(|receiver| receiver.last_received());
+            let last_received = client
+                .call_dry_run(&ink_e2e::alice(), &last_received, 0, None)
+                .await
+                .return_value();
+            assert_eq!(last_received, Some((bob, admin, 100)));
+
+            let to_rejecting = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.transfer_from(admin, rejecting, 100, vec![]));
+            let rejected = client
+                .call_dry_run(&ink_e2e::bob(), &to_rejecting, 0, None)
+                .await
+                .return_value();
+            assert!(matches!(
+                rejected,
+                Err(PSP22Error::SafeTransferCheckFailed(_))
+            ));
+
+            let balance_of = |account| {
+                build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.balance_of(account))
+            };
+            let accepted_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(accepting), 0, None)
+                .await
+                .return_value();
+            let rejected_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(rejecting), 0, None)
+                .await
+                .return_value();
+            assert_eq!(accepted_balance, 100);
+            assert_eq!(rejected_balance, 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "receiver_mock/Cargo.toml")]
+        async fn transfer_asks_contract_recipients(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            use receiver_mock::ReceiverMockRef;
+
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let token = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let accepting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(true),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate accepting receiver failed")
+                .account_id;
+            let rejecting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(false),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate rejecting receiver failed")
+                .account_id;
+
+            for receiver in [accepting, rejecting] {
+                let allow = build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.set_recipient_contract_allowed(receiver, true));
+                client
+                    .call(&ink_e2e::alice(), allow, 0, None)
+                    .await
+                    .expect("set_recipient_contract_allowed failed");
+            }
+
+            let to_accepting = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.transfer(accepting, 100, vec![]));
+            client
+                .call(&ink_e2e::alice(), to_accepting, 0, None)
+                .await
+                .expect("transfer to accepting receiver failed");
+
+            let last_received = build_message::<ReceiverMockRef>(accepting.clone())
+                .call(|receiver| receiver.last_received());
+            let last_received = client
+                .call_dry_run(&ink_e2e::alice(), &last_received, 0, None)
+                .await
+                .return_value();
+            assert_eq!(last_received, Some((admin, admin, 100)));
+
+            let to_rejecting = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.transfer(rejecting, 100, vec![]));
+            let rejected = client
+                .call_dry_run(&ink_e2e::alice(), &to_rejecting, 0, None)
+                .await
+                .return_value();
+            assert!(matches!(
+                rejected,
+                Err(PSP22Error::SafeTransferCheckFailed(_))
+            ));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "receiver_mock/Cargo.toml")]
+        async fn approve_and_call_lets_spender_pull_allowance(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            use receiver_mock::ReceiverMockRef;
+
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let token = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let vault = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(true),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate vault failed")
+                .account_id;
+            let rejecting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(false),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate rejecting spender failed")
+                .account_id;
+
+            for spender in [vault, rejecting] {
+                let allow = build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.set_recipient_contract_allowed(spender, true));
+                client
+                    .call(&ink_e2e::alice(), allow, 0, None)
+                    .await
+                    .expect("set_recipient_contract_allowed failed");
+            }
+
+            let balance_of = |account| {
+                build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.balance_of(account))
+            };
+            let allowance = |spender| {
+                build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.allowance(admin, spender))
+            };
+
+            let deposit = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.approve_and_call(vault, 300, vec![]));
+            client
+                .call(&ink_e2e::alice(), deposit, 0, None)
+                .await
+                .expect("approve_and_call to vault failed");
+
+            let vault_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(vault), 0, None)
+                .await
+                .return_value();
+            let admin_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(admin), 0, None)
+                .await
+                .return_value();
+            let vault_allowance = client
+                .call_dry_run(&ink_e2e::alice(), &allowance(vault), 0, None)
+                .await
+                .return_value();
+            assert_eq!(vault_balance, 300);
+            assert_eq!(admin_balance, 99_700);
+            assert_eq!(vault_allowance, 0);
+
+            let last_received = build_message::<ReceiverMockRef>(vault.clone())
+                .call(|receiver| receiver.last_received());
+            let last_received = client
+                .call_dry_run(&ink_e2e::alice(), &last_received, 0, None)
+                .await
+                .return_value();
+            assert_eq!(last_received, Some((vault, admin, 300)));
+
+            let to_rejecting = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.approve_and_call(rejecting, 300, vec![]));
+            let rejected = client
+                .call_dry_run(&ink_e2e::alice(), &to_rejecting, 0, None)
+                .await
+                .return_value();
+            assert_eq!(rejected, Err(PSP22Error::Custom(String::from("rejected"))));
+            assert!(client
+                .call(&ink_e2e::alice(), to_rejecting, 0, None)
+                .await
+                .is_err());
+
+            let rejecting_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(rejecting), 0, None)
+                .await
+                .return_value();
+            let admin_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(admin), 0, None)
+                .await
+                .return_value();
+            let rejecting_allowance = client
+                .call_dry_run(&ink_e2e::alice(), &allowance(rejecting), 0, None)
+                .await
+                .return_value();
+            assert_eq!(rejecting_balance, 0);
+            assert_eq!(admin_balance, 99_700);
+            assert_eq!(rejecting_allowance, 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test(additional_contracts = "receiver_mock/Cargo.toml")]
+        async fn flash_loan_pays_fee_to_treasury(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            use receiver_mock::ReceiverMockRef;
+
+            let admin = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let treasury = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+            let constructor = RedTokenRef::new(100_000, admin, 5u8);
+            let token = client
+                .instantiate("red", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let repaying = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(true),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate repaying borrower failed")
+                .account_id;
+            let defaulting = client
+                .instantiate(
+                    "receiver_mock",
+                    &ink_e2e::alice(),
+                    ReceiverMockRef::new(false),
+                    0,
+                    None,
+                )
+                .await
+                .expect("instantiate defaulting borrower failed")
+                .account_id;
+
+            let set_fee = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.set_flash_fee_bps(100));
+            client
+                .call(&ink_e2e::alice(), set_fee, 0, None)
+                .await
+                .expect("set_flash_fee_bps failed");
+            let propose = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.propose_fee_recipient(treasury));
+            client
+                .call(&ink_e2e::alice(), propose, 0, None)
+                .await
+                .expect("propose_fee_recipient failed");
+            let accept = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.accept_fee_recipient());
+            client
+                .call(&ink_e2e::charlie(), accept, 0, None)
+                .await
+                .expect("accept_fee_recipient failed");
+
+            // Each borrower holds the 1% fee on a 10_000 loan up front.
+            for borrower in [repaying, defaulting] {
+                let allow = build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.set_recipient_contract_allowed(borrower, true));
+                client
+                    .call(&ink_e2e::alice(), allow, 0, None)
+                    .await
+                    .expect("set_recipient_contract_allowed failed");
+                let fund = build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.transfer(borrower, 100, vec![]));
+                client
+                    .call(&ink_e2e::alice(), fund, 0, None)
+                    .await
+                    .expect("funding borrower failed");
+            }
+
+            let balance_of = |account| {
+                build_message::<RedTokenRef>(token.clone())
+                    .call(move |token| token.balance_of(account))
+            };
+            let total_supply =
+                build_message::<RedTokenRef>(token.clone()).call(|token| token.total_supply());
+
+            let repaid = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.flash_loan(repaying, 10_000, vec![]));
+            client
+                .call(&ink_e2e::alice(), repaid, 0, None)
+                .await
+                .expect("repaid flash_loan failed");
+
+            let treasury_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(treasury), 0, None)
+                .await
+                .return_value();
+            let repaying_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(repaying), 0, None)
+                .await
+                .return_value();
+            let supply = client
+                .call_dry_run(&ink_e2e::alice(), &total_supply, 0, None)
+                .await
+                .return_value();
+            assert_eq!(treasury_balance, 100);
+            assert_eq!(repaying_balance, 0);
+            assert_eq!(supply, 100_000);
+
+            let defaulted = build_message::<RedTokenRef>(token.clone())
+                .call(|token| token.flash_loan(defaulting, 10_000, vec![]));
+            let rejected = client
+                .call_dry_run(&ink_e2e::alice(), &defaulted, 0, None)
+                .await
+                .return_value();
+            assert_eq!(
+                rejected,
+                Err(PSP22Error::Custom(String::from("flash loan not repaid")))
+            );
+            assert!(client
+                .call(&ink_e2e::alice(), defaulted, 0, None)
+                .await
+                .is_err());
+
+            let treasury_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(treasury), 0, None)
+                .await
+                .return_value();
+            let admin_balance = client
+                .call_dry_run(&ink_e2e::alice(), &balance_of(admin), 0, None)
+                .await
+                .return_value();
+            let supply = client
+                .call_dry_run(&ink_e2e::alice(), &total_supply, 0, None)
+                .await
+                .return_value();
+            assert_eq!(treasury_balance, 100);
+            assert_eq!(admin_balance, 99_800);
+            assert_eq!(supply, 100_000);
+
+            Ok(())
         }
     }
 }
@@ -0,0 +1,184 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::receiver_mock::{ReceiverMock, ReceiverMockRef};
+
+/// A contract taking part in `red` transfers, approvals and flash loans for the
+/// e2e tests, either cooperating or refusing every time.
+#[ink::contract]
+pub mod receiver_mock {
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::CallFlags;
+    use ink::prelude::{string::String, vec::Vec};
+
+    /// Encodes like `red::PSP22Error`.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// The `red::PSP22` messages called back into; the trait and message names fix
+    /// the selectors.
+    #[ink::trait_definition]
+    pub trait PSP22 {
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    /// Mirrors `red::PSP22Receiver`.
+    #[ink::trait_definition]
+    pub trait PSP22Receiver {
+        #[ink(message)]
+        fn before_received(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    /// Mirrors `red::Spender`.
+    #[ink::trait_definition]
+    pub trait Spender {
+        #[ink(message)]
+        fn on_approval_received(
+            &mut self,
+            owner: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    /// Mirrors `red::FlashBorrower`.
+    #[ink::trait_definition]
+    pub trait FlashBorrower {
+        #[ink(message)]
+        fn on_flash_loan(
+            &mut self,
+            initiator: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error>;
+    }
+
+    #[ink(storage)]
+    pub struct ReceiverMock {
+        /// Whether to accept transfers, pull approved tokens and repay loans
+        accept: bool,
+        /// `(operator, from, value)` of the last accepted transfer
+        last_received: Option<(AccountId, AccountId, Balance)>,
+    }
+
+    impl ReceiverMock {
+        /// An accepting mock takes every transfer, pulls every approval like a
+        /// vault and repays flash loans from what it holds. A refusing one rejects
+        /// transfers and approvals and keeps flash-loaned tokens from being repaid.
+        #[ink(constructor)]
+        pub fn new(accept: bool) -> Self {
+            Self {
+                accept,
+                last_received: None,
+            }
+        }
+
+        /// Returns `(operator, from, value)` of the last accepted transfer.
+        #[ink(message)]
+        pub fn last_received(&self) -> Option<(AccountId, AccountId, Balance)> {
+            self.last_received
+        }
+
+        /// Returns a reference to the calling token.
+        fn token(&self) -> ink::contract_ref!(PSP22) {
+            self.env().caller().into()
+        }
+    }
+
+    impl PSP22Receiver for ReceiverMock {
+        #[ink(message)]
+        fn before_received(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            if !self.accept {
+                return Err(PSP22Error::Custom(String::from("rejected")));
+            }
+
+            self.last_received = Some((operator, from, value));
+
+            Ok(())
+        }
+    }
+
+    impl Spender for ReceiverMock {
+        /// Pulls the approved `value` into this contract, as a vault deposit would.
+        #[ink(message)]
+        fn on_approval_received(
+            &mut self,
+            owner: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            if !self.accept {
+                return Err(PSP22Error::Custom(String::from("rejected")));
+            }
+
+            // The pull re-enters this contract through `before_received`.
+            let to = self.env().account_id();
+            self.token()
+                .call_mut()
+                .transfer_from(owner, to, value, data)
+                .call_flags(CallFlags::default().set_allow_reentry(true))
+                .try_invoke()
+                .map_err(|_| PSP22Error::Custom(String::from("pull failed")))?
+                .map_err(|_| PSP22Error::Custom(String::from("pull failed")))?
+        }
+    }
+
+    impl FlashBorrower for ReceiverMock {
+        /// Repays by holding on to the loan, or sends it on to `initiator` when
+        /// refusing.
+        #[ink(message)]
+        fn on_flash_loan(
+            &mut self,
+            initiator: AccountId,
+            amount: Balance,
+            _fee: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            if self.accept {
+                return Ok(());
+            }
+
+            self.token()
+                .call_mut()
+                .transfer(initiator, amount, Vec::new())
+                .try_invoke()
+                .map_err(|_| PSP22Error::Custom(String::from("transfer failed")))?
+                .map_err(|_| PSP22Error::Custom(String::from("transfer failed")))?
+        }
+    }
+}